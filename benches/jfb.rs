@@ -26,3 +26,50 @@ fn bench_view_1000(b: &mut test::Bencher) {
         jfb.view();
     });
 }
+
+#[bench]
+fn bench_swap_1000(b: &mut test::Bencher) {
+    let harness = draco::testing::Harness::new(jfb::Jfb::new(true));
+    harness.send(jfb::Message::Append(1000));
+    b.iter(|| {
+        harness.send(jfb::Message::Swap);
+    });
+}
+
+// Renders a keyed list of 1000 rows in a new random order on every message.
+struct Reorder {
+    keys: Vec<u64>,
+    rng: xorshift::Xorshift128,
+}
+
+impl Application for Reorder {
+    type Message = ();
+
+    fn update(&mut self, _: (), _: &draco::Mailbox<()>) -> draco::Cmd<()> {
+        use xorshift::Rng;
+        self.rng.shuffle(&mut self.keys);
+        draco::Cmd::none()
+    }
+
+    fn view(&self) -> draco::VNode<()> {
+        use draco::html as h;
+        h::keyed::tbody()
+            .append(
+                self.keys
+                    .iter()
+                    .map(|&key| (key, h::tr().with(h::td().with(key)))),
+            )
+            .into()
+    }
+}
+
+#[bench]
+fn bench_reorder_1000(b: &mut test::Bencher) {
+    let harness = draco::testing::Harness::new(Reorder {
+        keys: (0..1000).collect(),
+        rng: xorshift::SeedableRng::from_seed(&[1, 2][..]),
+    });
+    b.iter(|| {
+        harness.send(());
+    });
+}
@@ -20,13 +20,19 @@ impl draco::Application for Counter {
     // This is the type our `view` will emit and `update` will handle.
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, _: &draco::Mailbox<Self::Message>) {
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         // We simply update `self.value` for the three possible messages.
         match message {
             Message::Increment => self.value += 1,
             Message::Decrement => self.value -= 1,
             Message::Reset => self.value = 0,
         }
+        // There are no side effects to perform.
+        draco::Cmd::none()
     }
 
     fn view(&self) -> draco::VNode<Self::Message> {
@@ -38,7 +44,7 @@ impl draco::Application for Counter {
                     .on(
                         // `click` is the event we want to listen to.
                         "click",
-                        // The closure takes one argument of `web_sys::Event` type.
+                        // The closure takes one argument of `draco::dom::Event` type.
                         // We don't need it here so we ignore it with `_`.
                         |_| {
                             // The closure returns the message we want our `update` function to
@@ -56,7 +56,11 @@ pub enum Message {
 impl draco::Application for Counters {
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, _mailbox: &draco::Mailbox<Self::Message>) {
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _mailbox: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         match message {
             Message::Append => self.counters.push(Counter::default()),
             Message::Counter(index, counter::Message::Remove) => {
@@ -66,6 +70,7 @@ impl draco::Application for Counters {
                 self.counters[index].update(message);
             }
         }
+        draco::Cmd::none()
     }
 
     fn view(&self) -> draco::VNode<Self::Message> {
@@ -33,7 +33,11 @@ pub enum Message {
 impl draco::Application for Form {
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, mailbox: &draco::Mailbox<Self::Message>) {
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _mailbox: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         use self::Message::*;
         match message {
             UpdateUsername(username) => {
@@ -50,7 +54,7 @@ impl draco::Application for Form {
             }
             Submit => {
                 self.is_submitting = true;
-                mailbox.send_after(1000, || Notify);
+                return draco::Cmd::timeout(1000, || Notify);
             }
             Notify => {
                 self.is_submitting = false;
@@ -60,6 +64,7 @@ impl draco::Application for Form {
                     .unwrap();
             }
         }
+        draco::Cmd::none()
     }
 
     fn view(&self) -> draco::VNode<Self::Message> {
@@ -44,7 +44,11 @@ enum Message {
 impl draco::Application for GitHubCommits {
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, mailbox: &draco::Mailbox<Self::Message>) {
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _mailbox: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         use self::Message::*;
         match message {
             Fetch => {
@@ -53,21 +57,24 @@ impl draco::Application for GitHubCommits {
                     self.repo
                 );
                 match url.parse::<reqwest::Url>() {
-                    Ok(url) => mailbox.spawn(
-                        // TODO: s/text()/json()/ and remove serde_json when it's implemented for WASM
-                        async {
-                            Ok(serde_json::from_str(
-                                &reqwest::get(url).await?.text().await?,
-                            )?)
-                        },
-                        Message::UpdateResponse,
-                    ),
+                    Ok(url) => {
+                        return draco::Cmd::spawn(
+                            // TODO: s/text()/json()/ and remove serde_json when it's implemented for WASM
+                            async {
+                                Ok(serde_json::from_str(
+                                    &reqwest::get(url).await?.text().await?,
+                                )?)
+                            },
+                            Message::UpdateResponse,
+                        );
+                    }
                     Err(err) => draco::console::error(&err.to_string()),
                 }
             }
             UpdateRepo(repo) => self.repo = repo,
             UpdateResponse(response) => self.response = Some(response),
         }
+        draco::Cmd::none()
     }
 
     fn view(&self) -> draco::VNode<Self::Message> {
@@ -89,7 +89,7 @@ impl Jfb {
             rows: Vec::new(),
             next_id: 1,
             selected_id: None,
-            rng: xorshift::SeedableRng::from_seed([0].as_slice()),
+            rng: xorshift::SeedableRng::from_seed([1, 2].as_slice()),
             keyed,
         }
     }
@@ -98,7 +98,11 @@ impl Jfb {
 impl draco::Application for Jfb {
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, mailbox: &draco::Mailbox<Self::Message>) {
+    fn update(
+        &mut self,
+        message: Self::Message,
+        mailbox: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         let Jfb {
             next_id,
             rng,
@@ -139,6 +143,7 @@ impl draco::Application for Jfb {
                 }
             }
         }
+        draco::Cmd::none()
     }
 
     fn view(&self) -> draco::VNode<Message> {
@@ -1,31 +1,11 @@
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
-const KEY: &str = "draco.examples.local_storage";
-
-#[derive(Debug)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct LocalStorage {
     value: String,
 }
 
-impl LocalStorage {
-    fn new() -> Self {
-        LocalStorage {
-            value: Self::storage()
-                .get_item(KEY)
-                .expect("get_item")
-                .unwrap_or("".into()),
-        }
-    }
-
-    fn storage() -> web_sys::Storage {
-        web_sys::window()
-            .expect("window")
-            .local_storage()
-            .expect("window.local_storage")
-            .expect("window.local_storage")
-    }
-}
-
 pub enum Message {
     Update(String),
 }
@@ -33,13 +13,17 @@ pub enum Message {
 impl draco::Application for LocalStorage {
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, _: &draco::Mailbox<Self::Message>) {
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         match message {
             Message::Update(value) => {
-                Self::storage().set_item(KEY, &value).expect("set_item");
                 self.value = value;
             }
         }
+        draco::Cmd::none()
     }
 
     fn view(&self) -> draco::VNode<Self::Message> {
@@ -62,10 +46,14 @@ impl draco::Application for LocalStorage {
     }
 }
 
+impl draco::persist::Persist for LocalStorage {
+    const KEY: &'static str = "draco.examples.local_storage";
+}
+
 #[wasm_bindgen(start)]
 pub fn start() {
-    draco::start(
-        LocalStorage::new(),
+    draco::persist::start(
+        LocalStorage::default(),
         draco::select("main").expect("<main>").into(),
     );
 }
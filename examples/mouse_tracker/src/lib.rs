@@ -6,7 +6,7 @@ use web_sys as web;
 struct MouseTracker {
     x: i32,
     y: i32,
-    subscription: Option<draco::Unsubscribe>,
+    is_tracking: bool,
 }
 
 enum Message {
@@ -17,39 +17,50 @@ enum Message {
 impl draco::Application for MouseTracker {
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, mailbox: &draco::Mailbox<Self::Message>) {
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _mailbox: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         match message {
-            Message::Toggle => {
-                if self.subscription.take().is_none() {
-                    self.subscription = Some(
-                        mailbox.subscribe(draco::subscription::OnWindow::new("mousemove"), |ev| {
-                            Message::Move(ev.dyn_into().unwrap())
-                        }),
-                    );
-                }
-            }
+            Message::Toggle => self.is_tracking = !self.is_tracking,
             Message::Move(mouse_event) => {
                 self.x = mouse_event.screen_x();
                 self.y = mouse_event.screen_y();
             }
         }
+        draco::Cmd::none()
+    }
+
+    fn subscriptions(&self) -> draco::Subscriptions<Self::Message> {
+        let subscriptions = draco::Subscriptions::new();
+        if self.is_tracking {
+            subscriptions.add(
+                "mousemove",
+                draco::subscription::OnWindow::new("mousemove"),
+                |ev| Message::Move(ev.dyn_into().unwrap()),
+            )
+        } else {
+            subscriptions
+        }
+    }
+
+    // Render at most once per frame, however many `mousemove` events arrive in between.
+    fn scheduler(&self) -> draco::Scheduler {
+        draco::Scheduler::AnimationFrame
     }
 
     fn view(&self) -> draco::VNode<Self::Message> {
         use draco::html as h;
         h::div()
             .with((
-                h::h1().with(if self.subscription.is_some() {
+                h::h1().with(if self.is_tracking {
                     "Tracking"
                 } else {
                     "Not Tracking"
                 }),
                 h::button()
-                    .with(if self.subscription.is_some() {
-                        "Stop"
-                    } else {
-                        "Start"
-                    })
+                    .with(if self.is_tracking { "Stop" } else { "Start" })
                     .on("click", |_| Message::Toggle),
                 h::div().with(("x = ", self.x, "; ", "y = ", self.y)),
             ))
@@ -59,9 +70,9 @@ impl draco::Application for MouseTracker {
 
 #[wasm_bindgen(start)]
 pub fn start() {
-    let mailbox = draco::start(
+    let app = draco::start(
         MouseTracker::default(),
         draco::select("main").expect("<main>").into(),
     );
-    mailbox.send(Message::Toggle);
+    app.send(Message::Toggle);
 }
@@ -5,31 +5,41 @@ use web_sys as web;
 #[derive(Default, Debug)]
 pub struct RefFocus {
     show: bool,
-    ref_: Option<web::Element>,
+    ref_: Option<draco::dom::Node>,
 }
 
 pub enum Message {
     Focus,
-    Ref(Option<web::Element>),
+    Ref(Option<draco::dom::Node>),
     Toggle,
 }
 
 impl draco::Application for RefFocus {
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, _: &draco::Mailbox<Self::Message>) {
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         match message {
             Message::Focus => {
                 if let Some(ref ref_) = self.ref_ {
-                    ref_.dyn_ref::<web::HtmlElement>()
+                    ref_.as_web()
+                        .and_then(|node| node.dyn_ref::<web::HtmlElement>())
                         .unwrap_throw()
                         .focus()
                         .unwrap_throw();
                 }
             }
             Message::Ref(ref_) => self.ref_ = ref_,
-            Message::Toggle => self.show = !self.show,
+            Message::Toggle => {
+                self.show = !self.show;
+                return draco::Cmd::none();
+            }
         }
+        // Neither focusing nor storing the ref changes what `view` renders.
+        draco::Cmd::skip_render()
     }
 
     fn view(&self) -> draco::VNode<Self::Message> {
@@ -11,20 +11,20 @@ enum Message {
     Navigate(Page),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 enum Page {
+    #[default]
     Index,
-    PostIndex { sort: Option<String> },
-    PostShow { id: i32, hash: Option<String> },
+    PostIndex {
+        sort: Option<String>,
+    },
+    PostShow {
+        id: i32,
+        hash: Option<String>,
+    },
     NotFound,
 }
 
-impl Default for Page {
-    fn default() -> Self {
-        Page::Index
-    }
-}
-
 impl draco::router::Route for Page {
     fn from_url(url: Url) -> Self {
         use draco::url::parse::*;
@@ -61,12 +61,23 @@ impl draco::router::Route for Page {
 impl draco::Application for Router {
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, _mailbox: &draco::Mailbox<Self::Message>) {
+    fn init(&mut self, mailbox: &draco::Mailbox<Self::Message>) -> draco::Cmd<Self::Message> {
+        // Sends the current route right away, so the first render already shows it.
+        mailbox.subscribe_forever(draco::router::Router::new(Hash), Message::Navigate);
+        draco::Cmd::none()
+    }
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _mailbox: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         match message {
             Message::Navigate(page) => {
                 self.page = page;
             }
         }
+        draco::Cmd::none()
     }
 
     fn view(&self) -> draco::VNode<Self::Message> {
@@ -135,10 +146,8 @@ impl draco::Application for Router {
 
 #[wasm_bindgen(start)]
 pub fn start() {
-    let mailbox = draco::start(
+    draco::start(
         Router::default(),
         draco::select("main").expect("<main>").into(),
     );
-
-    mailbox.subscribe_forever(draco::router::Router::new(Hash), Message::Navigate);
 }
@@ -21,12 +21,23 @@ enum Message {
 impl draco::Application for Router {
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, _mailbox: &draco::Mailbox<Self::Message>) {
+    fn init(&mut self, mailbox: &draco::Mailbox<Self::Message>) -> draco::Cmd<Self::Message> {
+        // Sends the current route right away, so the first render already shows it.
+        mailbox.subscribe_forever(draco::router::Router::new(Hash), Message::Navigate);
+        draco::Cmd::none()
+    }
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _mailbox: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         match message {
             Message::Navigate(url) => {
                 self.url = url;
             }
         }
+        draco::Cmd::none()
     }
 
     fn view(&self) -> draco::VNode<Self::Message> {
@@ -65,7 +76,5 @@ impl draco::Application for Router {
 
 #[wasm_bindgen(start)]
 pub fn start() {
-    let mailbox = draco::start(Router::new(), draco::select("main").expect("<main>").into());
-
-    mailbox.subscribe_forever(draco::router::Router::new(Hash), Message::Navigate);
+    draco::start(Router::new(), draco::select("main").expect("<main>").into());
 }
@@ -20,12 +20,24 @@ impl Clock {
 impl draco::Application for Clock {
     type Message = Message;
 
-    fn update(&mut self, message: Self::Message, _mailbox: &draco::Mailbox<Self::Message>) {
+    fn init(&mut self, mailbox: &draco::Mailbox<Self::Message>) -> draco::Cmd<Self::Message> {
+        mailbox.subscribe_forever(draco::subscription::AnimationFrame::new(), |_| {
+            Message::Tick
+        });
+        draco::Cmd::none()
+    }
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _mailbox: &draco::Mailbox<Self::Message>,
+    ) -> draco::Cmd<Self::Message> {
         match message {
             Message::Tick => {
                 self.date = js::Date::new_0();
             }
         }
+        draco::Cmd::none()
     }
 
     fn view(&self) -> draco::VNode<Self::Message> {
@@ -85,8 +97,5 @@ impl draco::Application for Clock {
 
 #[wasm_bindgen(start)]
 pub fn start() {
-    let mailbox = draco::start(Clock::new(), draco::select("main").expect("<main>").into());
-    mailbox.subscribe_forever(draco::subscription::AnimationFrame::new(), |_| {
-        Message::Tick
-    });
+    draco::start(Clock::new(), draco::select("main").expect("<main>").into());
 }
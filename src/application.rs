@@ -1,35 +1,172 @@
-use crate::{Mailbox, VNode, VText};
+use crate::delegation::{self, Delegation};
+use crate::dom::{self, Event, Node};
+use crate::mailbox::{Context, Stash};
+use crate::subscription::Subscriptions;
+use crate::task::{self, BoxFuture, Model, Task, Token};
+use crate::{Cmd, Mailbox, Scheduler, Unsubscribe, VNode, VText, S};
 use derivative::Derivative;
-use std::cell::{Cell, RefCell};
+use std::cell::{Cell, RefCell, RefMut};
 use std::rc::Rc;
+use std::task::Waker;
 use wasm_bindgen::UnwrapThrowExt;
 use web_sys as web;
 
 pub trait Application: Sized + 'static {
     type Message;
 
-    fn update(&mut self, _message: Self::Message, _mailbox: &Mailbox<Self::Message>) {}
+    // Called once before the first render.
+    fn init(&mut self, _mailbox: &Mailbox<Self::Message>) -> Cmd<Self::Message> {
+        Cmd::none()
+    }
+
+    fn update(
+        &mut self,
+        _message: Self::Message,
+        _mailbox: &Mailbox<Self::Message>,
+    ) -> Cmd<Self::Message> {
+        Cmd::none()
+    }
     fn view(&self) -> VNode<Self::Message>;
+
+    fn subscriptions(&self) -> Subscriptions<Self::Message> {
+        Subscriptions::new()
+    }
+
+    fn scheduler(&self) -> Scheduler {
+        Scheduler::Sync
+    }
+
+    // Handles `message` asynchronously if it returns a task, instead of passing it to `update`.
+    fn update_async(message: Self::Message, _model: Model<Self>) -> Result<Task, Self::Message> {
+        Err(message)
+    }
+
+    // Called once when the application is mounted.
+    fn middleware(&self) -> MiddlewareChain<Self> {
+        MiddlewareChain::empty()
+    }
+
+    // Called once when the application is mounted. If `true`, listeners in the view are not
+    // registered on their elements; instead a single listener per event type on the element
+    // containing the application finds and calls them. Events not known to bubble, and listeners
+    // inside components and portals, are still registered on their elements.
+    fn delegate_events(&self) -> bool {
+        false
+    }
+}
+
+// Sees every message on its way to `update`, e.g. to log it or to drop it while a modal is open.
+// Each middleware's `before` runs in order, and the `after`s in reverse order.
+pub trait Middleware<A: Application> {
+    // Returns the message to pass on, possibly a different one, or `None` to drop it.
+    fn before(&mut self, _app: &A, message: A::Message) -> Option<A::Message> {
+        Some(message)
+    }
+
+    // Called with the model once `update` has handled the message, or `update_async` has taken it
+    // over, with the message as the last middleware passed it on. If this or a later middleware
+    // dropped it, called with `None` instead. Middleware whose `before` did not run because an
+    // earlier one dropped the message are not called.
+    fn after(&mut self, _app: &A, _message: Option<&A::Message>) {}
+}
+
+pub struct MiddlewareChain<A: Application> {
+    middleware: Vec<Box<dyn Middleware<A>>>,
+    // `update` takes the message by value, so a copy is kept for `after`.
+    clone: Option<CloneFn<A::Message>>,
+}
+
+impl<A: Application> MiddlewareChain<A> {
+    pub fn new() -> Self
+    where
+        A::Message: Clone,
+    {
+        MiddlewareChain {
+            middleware: Vec::new(),
+            clone: Some(A::Message::clone),
+        }
+    }
+
+    fn empty() -> Self {
+        MiddlewareChain {
+            middleware: Vec::new(),
+            clone: None,
+        }
+    }
+
+    pub fn with(mut self, middleware: impl Middleware<A> + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+}
+
+impl<A: Application> Default for MiddlewareChain<A>
+where
+    A::Message: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[derive(Derivative)]
 #[derivative(Debug)]
-struct Instance<A: Application> {
+pub(crate) struct Instance<A: Application> {
     #[derivative(Debug = "ignore")]
     inner: Rc<Inner<A>>,
 }
 
+type Hook<T> = Box<dyn Fn(&T)>;
+type CloneFn<T> = fn(&T) -> T;
+type Scheduled = (Scheduler, Box<dyn FnOnce()>);
+
 struct Inner<A: Application> {
     app: RefCell<A>,
-    node: Cell<web::Node>,
     vnode: RefCell<VNode<A::Message>>,
     queue: RefCell<Vec<A::Message>>,
     is_updating: Cell<bool>,
     is_rendering: Cell<bool>,
+    is_unmounted: Cell<bool>,
+    // Replaying instances drop the messages sent to them; see `replay`.
+    is_replaying: Cell<bool>,
+    is_render_scheduled: Cell<bool>,
+    // Whether any message processed since the last render changed the model.
+    is_dirty: Cell<bool>,
+    is_flushing: Cell<bool>,
+    // Whether a task holds a `ModelGuard`.
+    is_locked: Cell<bool>,
+    // The model passed to `replace_app` while the current one was in use.
+    replacement: RefCell<Option<A>>,
+    // Tasks waiting for the `ModelGuard`.
+    waiters: RefCell<Vec<Waker>>,
+    scheduler: Cell<Scheduler>,
+    // Headless instances run scheduled renders only when `run_scheduled` is called.
+    scheduled: RefCell<Vec<Scheduled>>,
+    // Renders of components waiting for the next render.
+    renders: RefCell<Vec<Box<dyn FnOnce()>>>,
+    stash: Stash,
+    middleware: RefCell<MiddlewareChain<A>>,
+    observers: RefCell<Vec<Hook<A::Message>>>,
+    // Called with each message before the middleware, so that `replay` can handle it again.
+    recorders: RefCell<Vec<Hook<A::Message>>>,
+    watchers: RefCell<Vec<Hook<A>>>,
+    commands: RefCell<Option<Vec<Cmd<A::Message>>>>,
+    // Headless instances only track subscriptions by key, leaving the `Unsubscribe` empty.
+    subscriptions: RefCell<Vec<(S, Option<Unsubscribe>)>>,
+    delegation: RefCell<Option<Rc<Delegation>>>,
+    // Events delegated while the view was being patched, with their paths.
+    events: RefCell<Vec<(Vec<Node>, Event)>>,
+    // Running tasks started with a key.
+    keyed_tasks: RefCell<Vec<(S, Rc<Token>)>>,
+    // Headless instances run tasks only when `poll_tasks` is called.
+    tasks: RefCell<Vec<BoxFuture>>,
 }
 
 impl<A: Application> Instance<A> {
-    fn send(&self, message: A::Message) {
+    pub(crate) fn send(&self, message: A::Message) {
+        if self.inner.is_unmounted.get() || self.inner.is_replaying.get() {
+            return;
+        }
         self.push(message);
         self.update();
     }
@@ -38,48 +175,508 @@ impl<A: Application> Instance<A> {
         self.inner.queue.borrow_mut().push(message);
     }
 
-    fn update(&self) {
+    pub(crate) fn update(&self) {
         if self.inner.is_rendering.get() {
             return;
         }
 
         // If we were called from inside the `while` loop below, bail out; the message will be
-        // processed by the loop later.
-        if self.inner.is_updating.get() {
+        // processed by the loop later. The same goes for a task holding the model.
+        if self.inner.is_updating.get() || self.inner.is_locked.get() {
             return;
         }
 
         self.inner.is_updating.replace(true);
+        let mut changed = self.process(&self.mailbox());
+        if let Some(app) = self.inner.replacement.take() {
+            self.inner.app.replace(app);
+            self.inner.is_dirty.set(true);
+            changed = true;
+        }
+        if changed {
+            self.changed();
+        }
+        self.inner.is_updating.replace(false);
+        // Tasks which tried to lock the model meanwhile are waiting for it.
+        self.wake();
 
-        let mailbox = self.mailbox();
+        let is_pending = self.inner.is_dirty.get() || !self.inner.renders.borrow().is_empty();
+        if !self.inner.is_unmounted.get() && is_pending {
+            self.schedule_render();
+        } else {
+            self.inner.is_flushing.set(false);
+        }
+    }
 
+    // Returns whether any of the processed messages changed the model.
+    fn process(&self, mailbox: &Mailbox<A::Message>) -> bool {
+        let mut changed = false;
         while !self.inner.queue.borrow().is_empty() {
             let message = self.inner.queue.borrow_mut().remove(0);
-            self.inner.app.borrow_mut().update(message, &mailbox);
+            for recorder in &*self.inner.recorders.borrow() {
+                recorder(&message);
+            }
+            let (message, count) = self.intercept(message);
+            let message = match message {
+                Some(message) => message,
+                None => {
+                    self.after(count, None);
+                    continue;
+                }
+            };
+            for observer in &*self.inner.observers.borrow() {
+                observer(&message);
+            }
+            let copy = match self.inner.middleware.borrow().clone {
+                Some(clone) if count > 0 => Some(clone(&message)),
+                _ => None,
+            };
+            let token = Rc::new(Token::default());
+            let message = match A::update_async(message, Model::new(self.clone(), token.clone())) {
+                Ok(task) => {
+                    self.spawn(task, token);
+                    self.after(count, copy.as_ref());
+                    continue;
+                }
+                Err(message) => message,
+            };
+            let cmd = self.inner.app.borrow_mut().update(message, mailbox);
+            self.after(count, copy.as_ref());
+            if !cmd.skips_render() {
+                self.inner.is_dirty.set(true);
+                changed = true;
+            }
+            if !self.inner.is_unmounted.get() {
+                self.run(cmd, mailbox);
+            }
         }
+        changed
+    }
 
-        self.inner.is_updating.replace(false);
+    // Passes `message` through each middleware's `before`. Returns the message to handle, if any,
+    // and how many `before`s ran.
+    fn intercept(&self, mut message: A::Message) -> (Option<A::Message>, usize) {
+        let app = self.inner.app.borrow();
+        let mut chain = self.inner.middleware.borrow_mut();
+        for (index, middleware) in chain.middleware.iter_mut().enumerate() {
+            message = match middleware.before(&app, message) {
+                Some(message) => message,
+                None => return (None, index + 1),
+            };
+        }
+        (Some(message), chain.middleware.len())
+    }
+
+    // Calls the `after` of the first `count` middleware, in reverse order.
+    fn after(&self, count: usize, message: Option<&A::Message>) {
+        let app = self.inner.app.borrow();
+        let mut chain = self.inner.middleware.borrow_mut();
+        for middleware in chain.middleware[..count].iter_mut().rev() {
+            middleware.after(&app, message);
+        }
+    }
+
+    fn changed(&self) {
+        let app = self.inner.app.borrow();
+        for watcher in &*self.inner.watchers.borrow() {
+            watcher(&app);
+        }
+    }
 
+    pub(crate) fn start(&self) {
+        self.init();
         self.render();
     }
 
+    // Runs `Application::init` along with any messages it sends, without rendering.
+    fn init(&self) {
+        self.inner.is_updating.replace(true);
+        let mailbox = self.mailbox();
+        let cmd = self.inner.app.borrow_mut().init(&mailbox);
+        self.run(cmd, &mailbox);
+        self.process(&mailbox);
+        self.changed();
+        self.inner.is_updating.replace(false);
+    }
+
+    fn schedule_render(&self) {
+        let scheduler = self.inner.scheduler.get();
+        if self.inner.is_flushing.replace(false) || scheduler == Scheduler::Sync {
+            return self.render_scheduled();
+        }
+        if self.inner.is_render_scheduled.replace(true) {
+            return;
+        }
+        let cloned = self.clone();
+        let f = move || {
+            // The render may have been flushed already, or the application unmounted.
+            if cloned.inner.is_render_scheduled.get() && !cloned.inner.is_unmounted.get() {
+                cloned.render_scheduled();
+            }
+        };
+        if self.inner.commands.borrow().is_some() {
+            self.inner
+                .scheduled
+                .borrow_mut()
+                .push((scheduler, Box::new(f)));
+        } else {
+            scheduler.schedule(f);
+        }
+    }
+
+    // Runs `f`, a component's render, with the next render.
+    fn schedule(&self, f: Box<dyn FnOnce()>) {
+        if self.inner.scheduler.get() == Scheduler::Sync {
+            return f();
+        }
+        self.inner.renders.borrow_mut().push(f);
+        if !self.inner.is_updating.get() {
+            self.schedule_render();
+        }
+    }
+
+    pub(crate) fn set_scheduler(&self, scheduler: Scheduler) {
+        self.inner.scheduler.set(scheduler);
+    }
+
+    // Runs what a headless instance has scheduled with `scheduler`, in order.
+    pub(crate) fn run_scheduled(&self, scheduler: Scheduler) {
+        let (run, rest): (Vec<_>, _) = self
+            .inner
+            .scheduled
+            .take()
+            .into_iter()
+            .partition(|(s, _)| *s == scheduler);
+        self.inner.scheduled.replace(rest);
+        for (_, f) in run {
+            f();
+        }
+    }
+
+    pub(crate) fn flush(&self) {
+        if self.inner.is_updating.get() {
+            self.inner.is_flushing.set(true);
+        } else if self.inner.is_render_scheduled.get() && !self.inner.is_rendering.get() {
+            self.render_scheduled();
+        }
+    }
+
+    // Removes the application's DOM, stops its subscriptions and drops everything which could
+    // refer back to this instance, after which any message sent to it is ignored.
+    pub(crate) fn unmount(&self) {
+        if self.inner.is_unmounted.get() {
+            return;
+        }
+        self.inner.is_rendering.replace(true);
+        let vnode = self.inner.vnode.replace(VText::new("").into());
+        vnode.remove(&self.mailbox());
+        drop(vnode);
+        self.inner.is_rendering.replace(false);
+        self.inner.is_unmounted.replace(true);
+        // Deliver the messages sent by `did_remove`, e.g. `ref_(None)`.
+        self.update();
+        drop(self.inner.subscriptions.take());
+        if let Some(delegation) = self.inner.delegation.take() {
+            delegation.remove();
+        }
+        for (_, token) in self.inner.keyed_tasks.take() {
+            token.cancel();
+        }
+        // Waiting tasks see that the application is unmounted.
+        self.wake();
+        drop(self.inner.tasks.take());
+        drop(self.inner.renders.take());
+        drop(self.inner.stash.take());
+        drop(self.inner.observers.take());
+        drop(self.inner.recorders.take());
+        drop(self.inner.watchers.take());
+    }
+
+    fn hydrate(&self, node: Node) {
+        self.inner.is_dirty.replace(false);
+        self.inner.is_rendering.replace(true);
+        let mut vnode = self.inner.app.borrow().view();
+        let parent_node = dom::with(|dom| dom.parent(&node)).unwrap_throw();
+        vnode.hydrate(Some(node), &parent_node, &self.mailbox());
+        self.inner.vnode.replace(vnode);
+        self.subscribe();
+        self.inner.is_rendering.replace(false);
+        if !self.is_idle() {
+            self.update()
+        }
+    }
+
     fn render(&self) {
+        self.inner.is_render_scheduled.replace(false);
+        // Rendered once the task holding the model releases it.
+        if self.inner.is_locked.get() {
+            self.inner.is_dirty.set(true);
+            return;
+        }
+        self.inner.is_dirty.replace(false);
         self.inner.is_rendering.replace(true);
         let mut new_vnode = self.inner.app.borrow().view();
-        let new_node = new_vnode.patch(&mut self.inner.vnode.borrow_mut(), &self.mailbox());
+        new_vnode.patch(&mut self.inner.vnode.borrow_mut(), &self.mailbox());
         self.inner.vnode.replace(new_vnode);
-        self.inner.node.replace(new_node);
+        self.subscribe();
         self.inner.is_rendering.replace(false);
-        if !self.inner.queue.borrow().is_empty() {
+        self.wake();
+        self.render_components();
+        self.delegate_queued();
+        if !self.is_idle() {
             self.update()
         }
     }
 
-    fn mailbox(&self) -> Mailbox<A::Message> {
+    // Renders the components if the application itself has not changed.
+    fn render_scheduled(&self) {
+        if self.inner.is_dirty.get() {
+            return self.render();
+        }
+        self.inner.is_render_scheduled.replace(false);
+        self.render_components();
+    }
+
+    fn render_components(&self) {
+        for f in self.inner.renders.take() {
+            f();
+        }
+    }
+
+    // Called while `is_rendering` is set, so that messages sent synchronously by a new
+    // subscription are queued instead of reentering this function.
+    fn subscribe(&self) {
+        let new = self.inner.app.borrow().subscriptions().into_entries();
+        let mut old = self.inner.subscriptions.replace(Vec::new());
+        let is_headless = self.inner.commands.borrow().is_some();
+        let mailbox = self.mailbox();
+        let subscriptions = new
+            .into_iter()
+            .map(|(key, start)| {
+                if let Some(index) = old.iter().position(|(k, _)| *k == key) {
+                    return old.remove(index);
+                }
+                let unsubscribe = if is_headless {
+                    None
+                } else {
+                    let mailbox = mailbox.clone();
+                    Some(start(Rc::new(move |message| mailbox.send(message))))
+                };
+                (key, unsubscribe)
+            })
+            .collect();
+        self.inner.subscriptions.replace(subscriptions);
+        // Dropping the remaining `Unsubscribe`s stops them.
+        drop(old);
+    }
+
+    pub(crate) fn subscription_keys(&self) -> Vec<String> {
+        self.inner
+            .subscriptions
+            .borrow()
+            .iter()
+            .map(|(key, _)| key.to_string())
+            .collect()
+    }
+
+    pub(crate) fn mailbox(&self) -> Mailbox<A::Message> {
         let cloned = self.clone();
-        Mailbox::new(move |message| {
-            cloned.send(message);
-        })
+        let flush = self.clone();
+        let schedule = self.clone();
+        Mailbox::with_context(
+            move |message| {
+                cloned.send(message);
+            },
+            Context {
+                stash: self.inner.stash.clone(),
+                flush: Some(Rc::new(move || flush.flush())),
+                delegation: self.inner.delegation.borrow().clone(),
+                schedule: Some(Rc::new(move |f| schedule.schedule(f))),
+            },
+        )
+    }
+}
+
+impl<A: Application> Instance<A> {
+    pub(crate) fn app(&self) -> &RefCell<A> {
+        &self.inner.app
+    }
+
+    // Read from the view, as a component at its root may have replaced the node since.
+    pub(crate) fn node(&self) -> Node {
+        self.inner.vnode.borrow().node().unwrap_throw()
+    }
+
+    fn run(&self, cmd: Cmd<A::Message>, mailbox: &Mailbox<A::Message>) {
+        if cmd.is_none() {
+            return;
+        }
+        match *self.inner.commands.borrow_mut() {
+            Some(ref mut commands) => commands.push(cmd),
+            None => cmd.run(mailbox),
+        }
+    }
+
+    pub(crate) fn take_commands(&self) -> Vec<Cmd<A::Message>> {
+        self.inner
+            .commands
+            .borrow_mut()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn is_idle(&self) -> bool {
+        self.inner.queue.borrow().is_empty() && self.inner.replacement.borrow().is_none()
+    }
+
+    // Replaces the model without going through `update`, e.g. to show an earlier snapshot. If the
+    // model is in use, it is replaced once the messages being processed or the task holding it
+    // are done.
+    pub(crate) fn replace_app(&self, app: A) {
+        self.inner.replacement.replace(Some(app));
+        self.update();
+    }
+
+    // Handles `messages` with a headless copy of the application holding `app`, through the
+    // middleware and `update_async` like `process`, and returns the model afterwards. Tasks are
+    // polled after each message, so those waiting on anything but the model are left unfinished.
+    // Commands are not run and messages sent meanwhile are dropped, since `messages` already
+    // includes them.
+    pub(crate) fn replay(app: A, messages: impl IntoIterator<Item = A::Message>) -> A
+    where
+        A: Clone,
+    {
+        let mut vnode = VText::new("");
+        let node = vnode.create();
+        let instance = Instance::new(app, node, vnode.into(), true);
+        instance.inner.is_replaying.set(true);
+        // Renders of a headless instance wait for `run_scheduled`, so the view is never built.
+        instance.set_scheduler(Scheduler::AnimationFrame);
+        let mailbox = instance.mailbox();
+        for message in messages {
+            instance.push(message);
+            instance.inner.is_updating.replace(true);
+            instance.process(&mailbox);
+            instance.inner.is_updating.replace(false);
+            instance.poll_tasks();
+        }
+        let app = instance.inner.app.borrow().clone();
+        instance.unmount();
+        app
+    }
+
+    fn spawn(&self, task: Task, token: Rc<Token>) {
+        if let Some(key) = task.key {
+            let mut keyed_tasks = self.inner.keyed_tasks.borrow_mut();
+            keyed_tasks.retain(|(k, token)| {
+                if *k == key {
+                    token.cancel();
+                }
+                *k != key && !token.is_finished()
+            });
+            keyed_tasks.push((key, token.clone()));
+        }
+        let future = task::cancellable(task.future, token);
+        if self.inner.commands.borrow().is_some() {
+            self.inner.tasks.borrow_mut().push(future);
+        } else {
+            wasm_bindgen_futures::spawn_local(future);
+        }
+    }
+
+    // Polls every task of a headless instance once.
+    pub(crate) fn poll_tasks(&self) {
+        let tasks = self.inner.tasks.take();
+        let pending = task::poll(tasks);
+        let mut tasks = self.inner.tasks.borrow_mut();
+        let spawned = std::mem::replace(&mut *tasks, pending);
+        tasks.extend(spawned);
+    }
+
+    // Calls the delegated listeners for `event`, from its target up.
+    fn delegate(&self, event: Event) {
+        let delegation = match *self.inner.delegation.borrow() {
+            Some(ref delegation) => delegation.clone(),
+            None => return,
+        };
+        let path = match delegation::path(delegation.container(), &event) {
+            Some(path) => path,
+            None => return,
+        };
+        let mut messages = Vec::new();
+        match self.inner.vnode.try_borrow() {
+            Ok(vnode) => {
+                vnode.delegate(&path, &event.name(), &event, &mut messages);
+            }
+            // The patch dispatched the event, e.g. `focusout` on removing the focused element.
+            Err(_) => return self.inner.events.borrow_mut().push((path, event)),
+        }
+        let mailbox = self.mailbox();
+        for message in messages {
+            mailbox.send(message);
+        }
+    }
+
+    // Delegates the events queued during the render to the new view. It is too late for their
+    // listeners to call `prevent_default`.
+    fn delegate_queued(&self) {
+        let mut messages = Vec::new();
+        for (path, event) in self.inner.events.take() {
+            let vnode = self.inner.vnode.borrow();
+            vnode.delegate(&path, &event.name(), &event, &mut messages);
+        }
+        let mailbox = self.mailbox();
+        for message in messages {
+            mailbox.send(message);
+        }
+    }
+
+    pub(crate) fn lock(&self) -> Option<RefMut<'_, A>> {
+        if self.inner.is_updating.get() || self.inner.is_rendering.get() {
+            return None;
+        }
+        let app = self.inner.app.try_borrow_mut().ok()?;
+        self.inner.is_locked.set(true);
+        Some(app)
+    }
+
+    // Called once the `RefMut` returned by `lock` has been dropped.
+    pub(crate) fn unlock(&self) {
+        self.inner.is_locked.set(false);
+        self.inner.is_dirty.set(true);
+        self.changed();
+        self.update();
+        self.wake();
+    }
+
+    // Wakes `waker` the next time the model is unlocked, or updated or rendered.
+    pub(crate) fn wait(&self, waker: Waker) {
+        self.inner.waiters.borrow_mut().push(waker);
+    }
+
+    fn wake(&self) {
+        for waker in self.inner.waiters.take() {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn is_unmounted(&self) -> bool {
+        self.inner.is_unmounted.get()
+    }
+
+    // Calls `f` with the model after `init` and after each batch of messages which changed it.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn watch(&self, f: impl Fn(&A) + 'static) {
+        self.inner.watchers.borrow_mut().push(Box::new(f));
+    }
+
+    pub(crate) fn observe(&self, f: impl Fn(&A::Message) + 'static) {
+        self.inner.observers.borrow_mut().push(Box::new(f));
+    }
+
+    pub(crate) fn record(&self, f: impl Fn(&A::Message) + 'static) {
+        self.inner.recorders.borrow_mut().push(Box::new(f));
     }
 }
 
@@ -91,23 +688,117 @@ impl<A: Application> std::clone::Clone for Instance<A> {
     }
 }
 
-pub fn start<A: Application>(app: A, node: web::Node) -> Mailbox<A::Message> {
+impl<A: Application> Instance<A> {
+    // A headless instance collects the commands returned by `update` instead of running them and
+    // does not start any subscriptions.
+    fn new(app: A, node: Node, vnode: VNode<A::Message>, is_headless: bool) -> Self {
+        // Headless instances are driven synchronously by tests unless `set_scheduler` is called.
+        let scheduler = if is_headless {
+            Scheduler::Sync
+        } else {
+            app.scheduler()
+        };
+        let middleware = app.middleware();
+        let delegate_events = app.delegate_events();
+        let container = if delegate_events {
+            dom::with(|dom| dom.parent(&node))
+        } else {
+            None
+        };
+        let instance = Instance {
+            inner: Rc::new(Inner {
+                app: RefCell::new(app),
+                vnode: RefCell::new(vnode),
+                is_updating: Cell::new(false),
+                is_rendering: Cell::new(false),
+                is_unmounted: Cell::new(false),
+                is_replaying: Cell::new(false),
+                is_render_scheduled: Cell::new(false),
+                is_dirty: Cell::new(false),
+                is_flushing: Cell::new(false),
+                is_locked: Cell::new(false),
+                replacement: RefCell::new(None),
+                waiters: RefCell::new(Vec::new()),
+                scheduler: Cell::new(scheduler),
+                scheduled: RefCell::new(Vec::new()),
+                renders: RefCell::new(Vec::new()),
+                middleware: RefCell::new(middleware),
+                stash: Stash::default(),
+                queue: RefCell::new(Vec::new()),
+                observers: RefCell::new(Vec::new()),
+                recorders: RefCell::new(Vec::new()),
+                watchers: RefCell::new(Vec::new()),
+                commands: RefCell::new(if is_headless { Some(Vec::new()) } else { None }),
+                subscriptions: RefCell::new(Vec::new()),
+                delegation: RefCell::new(None),
+                events: RefCell::new(Vec::new()),
+                keyed_tasks: RefCell::new(Vec::new()),
+                tasks: RefCell::new(Vec::new()),
+            }),
+        };
+        if let Some(container) = container {
+            let inner = Rc::downgrade(&instance.inner);
+            let delegation = Delegation::new(container, move |event| {
+                if let Some(inner) = inner.upgrade() {
+                    Instance { inner }.delegate(event);
+                }
+            });
+            instance.inner.delegation.replace(Some(Rc::new(delegation)));
+        }
+        instance
+    }
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct AppHandle<A: Application> {
+    instance: Instance<A>,
+}
+
+impl<A: Application> AppHandle<A> {
+    pub(crate) fn new(instance: Instance<A>) -> Self {
+        AppHandle { instance }
+    }
+
+    pub fn mailbox(&self) -> Mailbox<A::Message> {
+        self.instance.mailbox()
+    }
+
+    pub fn send(&self, message: A::Message) {
+        self.instance.send(message);
+    }
+
+    // Renders any pending changes right away.
+    pub fn flush(&self) {
+        self.instance.flush();
+    }
+
+    pub fn unmount(self) {
+        self.instance.unmount();
+    }
+}
+
+pub fn start<A: Application>(app: A, node: web::Node) -> AppHandle<A> {
+    let instance = mount(app, Node::Web(node), false);
+    instance.start();
+    AppHandle { instance }
+}
+
+// Replaces `node` with a placeholder for the application to render into once `start`ed.
+pub(crate) fn mount<A: Application>(app: A, node: Node, is_headless: bool) -> Instance<A> {
     let mut vnode = VText::new("!");
-    let new_node = vnode.create().into();
-    node.parent_node()
-        .unwrap_throw()
-        .replace_child(&new_node, &node)
-        .unwrap_throw();
-    let instance = Instance {
-        inner: Rc::new(Inner {
-            app: RefCell::new(app),
-            node: Cell::new(new_node),
-            vnode: RefCell::new(vnode.into()),
-            is_updating: Cell::new(false),
-            is_rendering: Cell::new(false),
-            queue: RefCell::new(Vec::new()),
-        }),
-    };
-    instance.render();
-    instance.mailbox()
+    let new_node = vnode.create();
+    dom::with(|dom| {
+        let parent_node = dom.parent(&node).unwrap_throw();
+        dom.replace_child(&parent_node, &new_node, &node);
+    });
+    Instance::new(app, new_node, vnode.into(), is_headless)
+}
+
+pub fn hydrate<A: Application>(app: A, node: web::Node) -> AppHandle<A> {
+    let node = Node::Web(node);
+    let instance = Instance::new(app, node.clone(), VText::new("").into(), false);
+    instance.init();
+    instance.hydrate(node);
+    AppHandle { instance }
 }
@@ -1,13 +1,15 @@
-use crate::{Attribute, Listener, Mailbox, Property};
+use crate::dom::Node;
+use crate::{Attribute, Class, Listener, Mailbox, Property, Style};
 use derivative::Derivative;
 use std::rc::Rc;
-use web_sys as web;
 
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
 pub enum Aspect<Message> {
     Attribute(Attribute),
     Property(Property),
+    Style(Style),
+    Class(Class),
     Listener(Listener<Message>),
 }
 
@@ -19,54 +21,134 @@ impl<Message: 'static> Aspect<Message> {
         match self {
             Aspect::Attribute(attribute) => Aspect::Attribute(attribute),
             Aspect::Property(property) => Aspect::Property(property),
+            Aspect::Style(style) => Aspect::Style(style),
+            Aspect::Class(class) => Aspect::Class(class),
             Aspect::Listener(listener) => Aspect::Listener(listener.do_map(f)),
         }
     }
+
+    // Identifies the aspect among an element's aspects: its name and what kind of aspect it is.
+    fn key(&self) -> (u8, &str) {
+        match self {
+            Aspect::Attribute(attribute) => (0, attribute.name()),
+            Aspect::Property(property) => (1, property.name()),
+            Aspect::Style(style) => (2, style.name()),
+            Aspect::Class(class) => (3, class.name()),
+            Aspect::Listener(listener) => (4, listener.name()),
+        }
+    }
 }
 
-pub fn patch<Message>(
+// Below this many old aspects, finding each new aspect's old one by scanning them all is faster
+// than sorting them first.
+const LINEAR_SCAN: usize = 16;
+
+pub fn patch<Message: 'static>(
     new_aspects: &mut [Aspect<Message>],
     old_aspects: &[Aspect<Message>],
-    element: &web::Element,
+    element: &Node,
     mailbox: &Mailbox<Message>,
 ) {
-    macro_rules! find {
-        ($aspects:expr, $name:expr, $ty:ident) => {
-            $aspects
-                .iter()
-                .filter_map(|aspect| match aspect {
-                    Aspect::$ty(aspect) if aspect.name() == $name => Some(aspect),
-                    _ => None,
+    if old_aspects.len() <= LINEAR_SCAN {
+        return patch_linear(new_aspects, old_aspects, element, mailbox);
+    }
+
+    // Old aspects sorted by key, each claimed by the first new aspect with the same key. The
+    // sort is stable, so duplicates are claimed in order.
+    let mut index = old_aspects
+        .iter()
+        .map(|old_aspect| (old_aspect.key(), old_aspect, false))
+        .collect::<Vec<_>>();
+    index.sort_by_key(|(key, _, _)| *key);
+
+    for new_aspect in new_aspects.iter_mut() {
+        let old_aspect = {
+            let key = new_aspect.key();
+            let start = index.partition_point(|(old_key, _, _)| *old_key < key);
+            index[start..]
+                .iter_mut()
+                .take_while(|(old_key, _, _)| *old_key == key)
+                .find(|(_, _, claimed)| !*claimed)
+                .map(|(_, old_aspect, claimed)| {
+                    *claimed = true;
+                    *old_aspect
                 })
-                .next()
         };
+        patch_one(new_aspect, old_aspect, element, mailbox);
     }
 
+    for (position, (key, old_aspect, claimed)) in index.iter().enumerate() {
+        if !*claimed {
+            let is_first = position == 0 || index[position - 1].0 != *key;
+            remove(old_aspect, is_first, element);
+        }
+    }
+}
+
+// `patch` without allocating, marking the claimed old aspects in a bit set.
+fn patch_linear<Message: 'static>(
+    new_aspects: &mut [Aspect<Message>],
+    old_aspects: &[Aspect<Message>],
+    element: &Node,
+    mailbox: &Mailbox<Message>,
+) {
+    let mut claimed = 0u32;
     for new_aspect in new_aspects.iter_mut() {
-        match new_aspect {
-            Aspect::Attribute(attribute) => {
-                attribute.patch(find!(old_aspects, attribute.name(), Attribute), element)
-            }
-            Aspect::Property(property) => {
-                property.patch(find!(old_aspects, property.name(), Property), element)
-            }
-            Aspect::Listener(listener) => listener.attach(element, mailbox),
+        let key = new_aspect.key();
+        let old_aspect = old_aspects
+            .iter()
+            .enumerate()
+            .find(|(position, old_aspect)| claimed & 1 << position == 0 && old_aspect.key() == key)
+            .map(|(position, old_aspect)| {
+                claimed |= 1 << position;
+                old_aspect
+            });
+        patch_one(new_aspect, old_aspect, element, mailbox);
+    }
+
+    for (position, old_aspect) in old_aspects.iter().enumerate() {
+        if claimed & 1 << position == 0 {
+            let key = old_aspect.key();
+            let is_first = !old_aspects[..position].iter().any(|a| a.key() == key);
+            remove(old_aspect, is_first, element);
         }
     }
-    for old_aspect in old_aspects {
-        match old_aspect {
-            Aspect::Attribute(attribute) => {
-                if find!(new_aspects, attribute.name(), Attribute).is_none() {
-                    attribute.remove(element);
-                }
-            }
-            Aspect::Property(property) => {
-                if find!(new_aspects, property.name(), Property).is_none() {
-                    property.remove(element);
-                }
+}
+
+fn patch_one<Message: 'static>(
+    new_aspect: &mut Aspect<Message>,
+    old_aspect: Option<&Aspect<Message>>,
+    element: &Node,
+    mailbox: &Mailbox<Message>,
+) {
+    macro_rules! old {
+        ($ty:ident) => {
+            match old_aspect {
+                Some(Aspect::$ty(old)) => Some(old),
+                _ => None,
             }
-            Aspect::Listener(listener) => listener.detach(element),
-        }
+        };
+    }
+    match new_aspect {
+        Aspect::Attribute(attribute) => attribute.patch(old!(Attribute), element),
+        Aspect::Property(property) => property.patch(old!(Property), element),
+        Aspect::Style(style) => style.patch(old!(Style), element),
+        Aspect::Class(class) => class.patch(old!(Class), element),
+        Aspect::Listener(listener) => listener.patch(old!(Listener), element, mailbox),
+    }
+}
+
+// Removes an old aspect no new aspect claimed. Listeners each have their own closure to remove.
+// Other aspects are only removed if no new aspect has their key, i.e. if the first old one with
+// it was not claimed either.
+fn remove<Message: 'static>(old_aspect: &Aspect<Message>, is_first: bool, element: &Node) {
+    match old_aspect {
+        Aspect::Listener(listener) => listener.remove(element),
+        _ if !is_first => {}
+        Aspect::Attribute(attribute) => attribute.remove(element),
+        Aspect::Property(property) => property.remove(element),
+        Aspect::Style(style) => style.remove(element),
+        Aspect::Class(class) => class.remove(element),
     }
 }
 
@@ -82,6 +164,18 @@ impl<Message> From<Property> for Aspect<Message> {
     }
 }
 
+impl<Message> From<Style> for Aspect<Message> {
+    fn from(style: Style) -> Self {
+        Aspect::Style(style)
+    }
+}
+
+impl<Message> From<Class> for Aspect<Message> {
+    fn from(class: Class) -> Self {
+        Aspect::Class(class)
+    }
+}
+
 impl<Message> From<Listener<Message>> for Aspect<Message> {
     fn from(listener: Listener<Message>) -> Self {
         Aspect::Listener(listener)
@@ -1,6 +1,5 @@
-use crate::S;
-use wasm_bindgen::UnwrapThrowExt;
-use web_sys as web;
+use crate::dom::{self, Node};
+use crate::{render, S};
 
 #[derive(Debug, PartialEq)]
 pub struct Attribute {
@@ -24,18 +23,17 @@ impl Attribute {
         &self.value
     }
 
-    pub fn patch(&self, old_attribute: Option<&Attribute>, element: &web::Element) {
+    pub fn patch(&self, old_attribute: Option<&Attribute>, element: &Node) {
         if Some(self) != old_attribute {
-            element
-                .set_attribute(
-                    wasm_bindgen::intern(&self.name),
-                    wasm_bindgen::intern(&self.value),
-                )
-                .unwrap_throw()
+            dom::with(|dom| dom.set_attribute(element, &self.name, &self.value));
         }
     }
 
-    pub fn remove(&self, element: &web::Element) {
-        element.remove_attribute(&self.name).unwrap_throw();
+    pub fn remove(&self, element: &Node) {
+        dom::with(|dom| dom.remove_attribute(element, &self.name));
+    }
+
+    pub(crate) fn write_html(&self, html: &mut String) {
+        render::attribute(html, &self.name, Some(&self.value));
     }
 }
@@ -0,0 +1,227 @@
+use crate::dom::{self, Event, Node};
+use crate::{hydrate, Mailbox, VNode};
+use derivative::Derivative;
+use std::any::Any;
+use std::hash::{Hash, Hasher};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use wasm_bindgen::UnwrapThrowExt;
+
+type Fallback<Message> = Rc<dyn Fn(&str) -> VNode<Message>>;
+
+// Builds its children lazily and renders `fallback` instead if building or patching them panics,
+// leaving the rest of the application untouched. Once failed, the children are tried again on
+// every render, or only when the value passed to `inputs` changes if there is one.
+//
+// This is a panic boundary, not a general error boundary: it catches Rust panics by unwinding,
+// so it only exists in builds with `panic=unwind`, which on `wasm32-unknown-unknown` is not the
+// default. DOM calls which fail in the browser throw a JavaScript exception through
+// `unwrap_throw`, which is not caught. When patching panics halfway, the old children are
+// removed, but nodes the failed patch had already created elsewhere, e.g. in a `Portal`, may be
+// left in the document.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct Boundary<Message: 'static> {
+    #[derivative(Debug = "ignore")]
+    view: Box<dyn Fn() -> VNode<Message>>,
+    #[derivative(Debug = "ignore")]
+    fallback: Fallback<Message>,
+    #[derivative(Debug = "ignore")]
+    on_error: Option<Rc<dyn Fn(String) -> Message>>,
+    inputs: Option<u64>,
+    // The message of the panic while the fallback is shown.
+    error: Option<String>,
+    vnode: Option<Box<VNode<Message>>>,
+}
+
+impl<Message: 'static> Boundary<Message> {
+    pub fn new(
+        view: impl Fn() -> VNode<Message> + 'static,
+        fallback: impl Fn(&str) -> VNode<Message> + 'static,
+    ) -> Self {
+        Boundary {
+            view: Box::new(view),
+            fallback: Rc::new(fallback),
+            on_error: None,
+            inputs: None,
+            error: None,
+            vnode: None,
+        }
+    }
+
+    // Sends a message with the panic's message when the children start failing. It is not sent
+    // again while they keep failing, so that handling it cannot cause a render loop.
+    pub fn on_error(mut self, f: impl Fn(String) -> Message + 'static) -> Self {
+        self.on_error = Some(Rc::new(f));
+        self
+    }
+
+    // The values `view` depends on. Failed children are only tried again once these change.
+    pub fn inputs<T: Hash>(mut self, t: T) -> Self {
+        let mut hasher = fxhash::FxHasher::default();
+        t.hash(&mut hasher);
+        self.inputs = Some(hasher.finish());
+        self
+    }
+
+    pub fn create(&mut self, mailbox: &Mailbox<Message>) -> Node {
+        let (vnode, node) = self
+            .try_view(|vnode| vnode.create(mailbox))
+            .unwrap_or_else(|error| {
+                let mut vnode = self.fail(error, true, mailbox);
+                let node = vnode.create(mailbox);
+                (vnode, node)
+            });
+        self.vnode = Some(Box::new(vnode));
+        node
+    }
+
+    pub fn hydrate(
+        &mut self,
+        node: Option<Node>,
+        parent: &Node,
+        mailbox: &Mailbox<Message>,
+    ) -> Node {
+        let found = node.clone();
+        let (vnode, node) = self
+            .try_view(|vnode| vnode.hydrate(node, parent, mailbox))
+            .unwrap_or_else(|error| {
+                let mut vnode = self.fail(error, true, mailbox);
+                let node = vnode.create(mailbox);
+                hydrate::replace(parent, &node, found.as_ref());
+                (vnode, node)
+            });
+        self.vnode = Some(Box::new(vnode));
+        node
+    }
+
+    pub fn patch(&mut self, old: &mut Self, mailbox: &Mailbox<Message>) -> Node {
+        let mut old_vnode = old.vnode.take().unwrap_throw();
+        let old_node = old_vnode.node().unwrap_throw();
+        if let Some(error) = old.error.take() {
+            if self.inputs.is_some() && self.inputs == old.inputs {
+                let mut vnode = self.fail(error, false, mailbox);
+                let node = vnode.patch(&mut old_vnode, mailbox);
+                self.vnode = Some(Box::new(vnode));
+                return node;
+            }
+            old.error = Some(error);
+        }
+        let result = self.try_view(|vnode| vnode.patch(&mut old_vnode, mailbox));
+        let (vnode, node) = result.unwrap_or_else(|error| {
+            // The old children may be half patched, so replace them all.
+            let mut vnode = self.fail(error, old.error.is_none(), mailbox);
+            let node = vnode.create(mailbox);
+            dom::with(|dom| {
+                let parent_node = dom.parent(&old_node).unwrap_throw();
+                dom.insert_before(&parent_node, &node, Some(&old_node));
+            });
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| old_vnode.remove(mailbox)));
+            (vnode, node)
+        });
+        self.vnode = Some(Box::new(vnode));
+        node
+    }
+
+    pub fn node(&self) -> Option<Node> {
+        self.vnode.as_ref()?.node()
+    }
+
+    pub(crate) fn nodes(&self, nodes: &mut Vec<Node>) {
+        if let Some(ref vnode) = self.vnode {
+            vnode.nodes(nodes);
+        }
+    }
+
+    pub(crate) fn delegate(
+        &self,
+        path: &[Node],
+        name: &str,
+        event: &Event,
+        messages: &mut Vec<Message>,
+    ) -> bool {
+        match self.vnode {
+            Some(ref vnode) => vnode.delegate(path, name, event, messages),
+            None => false,
+        }
+    }
+
+    pub fn did_remove(&self, mailbox: &Mailbox<Message>) {
+        if let Some(ref vnode) = self.vnode {
+            vnode.did_remove(mailbox);
+        }
+    }
+
+    pub(crate) fn write_html(&self, html: &mut String) {
+        if let Some(ref vnode) = self.vnode {
+            return vnode.write_html(html);
+        }
+        let len = html.len();
+        if let Err(payload) =
+            panic::catch_unwind(AssertUnwindSafe(|| (self.view)().write_html(html)))
+        {
+            html.truncate(len);
+            (self.fallback)(&error(payload)).write_html(html);
+        }
+    }
+
+    pub(crate) fn do_map<NewMessage: 'static>(
+        self,
+        f: Rc<impl Fn(Message) -> NewMessage + 'static>,
+    ) -> Boundary<NewMessage> {
+        let Boundary {
+            view,
+            fallback,
+            on_error,
+            inputs,
+            error,
+            vnode: _,
+        } = self;
+        let (g, h) = (f.clone(), f.clone());
+        Boundary {
+            view: Box::new(move || view().do_map(f.clone())),
+            fallback: Rc::new(move |error| fallback(error).do_map(g.clone())),
+            on_error: on_error.map(|on_error| {
+                Rc::new(move |error| h(on_error(error))) as Rc<dyn Fn(String) -> NewMessage>
+            }),
+            inputs,
+            error,
+            vnode: None,
+        }
+    }
+
+    // Builds the children and passes them to `f`, catching a panic in either.
+    fn try_view(
+        &self,
+        f: impl FnOnce(&mut VNode<Message>) -> Node,
+    ) -> Result<(VNode<Message>, Node), String> {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut vnode = (self.view)();
+            let node = f(&mut vnode);
+            (vnode, node)
+        }))
+        .map_err(error)
+    }
+
+    // Enters the error state, returning the fallback to render.
+    fn fail(&mut self, error: String, report: bool, mailbox: &Mailbox<Message>) -> VNode<Message> {
+        if report {
+            if let Some(ref on_error) = self.on_error {
+                mailbox.send(on_error(error.clone()));
+            }
+        }
+        let vnode = (self.fallback)(&error);
+        self.error = Some(error);
+        vnode
+    }
+}
+
+fn error(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(string) => *string,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(str) => (*str).into(),
+            Err(_) => "unknown error".into(),
+        },
+    }
+}
@@ -0,0 +1,29 @@
+use crate::dom::{self, Node};
+use crate::S;
+
+// A single class token, added and removed through `Element.classList` so that classes added by
+// anything other than the view are left alone.
+#[derive(Debug, PartialEq)]
+pub struct Class {
+    name: S,
+}
+
+impl Class {
+    pub fn new(name: impl Into<S>) -> Self {
+        Self { name: name.into() }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn patch(&self, old_class: Option<&Class>, element: &Node) {
+        if old_class.is_none() {
+            dom::with(|dom| dom.add_class(element, &self.name));
+        }
+    }
+
+    pub fn remove(&self, element: &Node) {
+        dom::with(|dom| dom.remove_class(element, &self.name));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::router::{self, Mode, Route};
+use crate::url::Url;
+use crate::{Mailbox, S};
+use derivative::Derivative;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+#[derive(Derivative, Default)]
+#[derivative(Debug(bound = ""))]
+pub enum Cmd<Message> {
+    #[default]
+    None,
+    Batch(Vec<Cmd<Message>>),
+    // Sends the message returned by `f` after `timeout` milliseconds.
+    Timeout {
+        timeout: i32,
+        label: Option<S>,
+        #[derivative(Debug = "ignore")]
+        f: Box<dyn Fn() -> Message>,
+    },
+    Spawn {
+        label: Option<S>,
+        #[derivative(Debug = "ignore")]
+        future: Pin<Box<dyn Future<Output = Message>>>,
+    },
+    Navigate(Mode, Url),
+    // Tells the runtime the model did not change, so there is nothing new to render.
+    SkipRender,
+}
+
+impl<Message: 'static> Cmd<Message> {
+    pub fn none() -> Self {
+        Cmd::None
+    }
+
+    pub fn batch(cmds: impl IntoIterator<Item = Cmd<Message>>) -> Self {
+        Cmd::Batch(cmds.into_iter().collect())
+    }
+
+    pub fn timeout(timeout: i32, f: impl Fn() -> Message + 'static) -> Self {
+        Cmd::Timeout {
+            timeout,
+            label: None,
+            f: Box::new(f),
+        }
+    }
+
+    pub fn spawn<F>(future: F, f: impl Fn(F::Output) -> Message + 'static) -> Self
+    where
+        F: Future + 'static,
+    {
+        Cmd::Spawn {
+            label: None,
+            future: Box::pin(async move { f(future.await) }),
+        }
+    }
+
+    // Names a timeout or a spawned future, e.g. to tell commands apart in tests. Other commands
+    // are returned unchanged.
+    pub fn label(mut self, name: impl Into<S>) -> Self {
+        if let Cmd::Timeout { ref mut label, .. } | Cmd::Spawn { ref mut label, .. } = self {
+            *label = Some(name.into());
+        }
+        self
+    }
+
+    pub fn navigate<R: Route>(mode: Mode, r: &R) -> Self {
+        Cmd::Navigate(mode, r.to_url())
+    }
+
+    pub fn skip_render() -> Self {
+        Cmd::SkipRender
+    }
+
+    pub fn is_none(&self) -> bool {
+        match self {
+            Cmd::None | Cmd::SkipRender => true,
+            Cmd::Batch(cmds) => cmds.iter().all(Cmd::is_none),
+            _ => false,
+        }
+    }
+
+    // A batch skips the render if any of its commands does, while the others still run.
+    pub fn skips_render(&self) -> bool {
+        match self {
+            Cmd::SkipRender => true,
+            Cmd::Batch(cmds) => cmds.iter().any(Cmd::skips_render),
+            _ => false,
+        }
+    }
+
+    pub fn map<NewMessage: 'static>(
+        self,
+        f: impl Fn(Message) -> NewMessage + 'static,
+    ) -> Cmd<NewMessage> {
+        self.do_map(Rc::new(f))
+    }
+
+    fn do_map<NewMessage: 'static>(self, f: Rc<dyn Fn(Message) -> NewMessage>) -> Cmd<NewMessage> {
+        match self {
+            Cmd::None => Cmd::None,
+            Cmd::Batch(cmds) => {
+                Cmd::Batch(cmds.into_iter().map(|cmd| cmd.do_map(f.clone())).collect())
+            }
+            Cmd::Timeout {
+                timeout,
+                label,
+                f: g,
+            } => Cmd::Timeout {
+                timeout,
+                label,
+                f: Box::new(move || f(g())),
+            },
+            Cmd::Spawn { label, future } => Cmd::Spawn {
+                label,
+                future: Box::pin(async move { f(future.await) }),
+            },
+            Cmd::Navigate(mode, url) => Cmd::Navigate(mode, url),
+            Cmd::SkipRender => Cmd::SkipRender,
+        }
+    }
+
+    pub fn run(self, mailbox: &Mailbox<Message>) {
+        match self {
+            Cmd::None | Cmd::SkipRender => {}
+            Cmd::Batch(cmds) => {
+                for cmd in cmds {
+                    cmd.run(mailbox);
+                }
+            }
+            Cmd::Timeout { timeout, f, .. } => mailbox.send_after(timeout, f),
+            Cmd::Spawn { future, .. } => mailbox.spawn(future, |message| message),
+            Cmd::Navigate(mode, url) => router::push(mode, &url),
+        }
+    }
+}
@@ -0,0 +1,445 @@
+use crate::dom::Node;
+use crate::mailbox::{Context, Schedule, Stash};
+use crate::{Cmd, Mailbox, VNode};
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt;
+use std::rc::{Rc, Weak};
+use wasm_bindgen::UnwrapThrowExt;
+
+// A widget with its own model and messages, embedded in its parent's view with `VComponent`.
+// It renders again whenever it handles a message or its parent passes it different props. Renders
+// after messages wait for the application's scheduler, like the application's own.
+pub trait Component: Sized + 'static {
+    type Props: PartialEq + 'static;
+    type Message: 'static;
+    // Sent to the parent with `Scope::emit`.
+    type Event: 'static;
+
+    fn create(props: &Self::Props) -> Self;
+
+    fn update(
+        &mut self,
+        props: &Self::Props,
+        message: Self::Message,
+        scope: &Scope<Self>,
+    ) -> Cmd<Self::Message>;
+
+    fn view(&self, props: &Self::Props) -> VNode<Self::Message>;
+
+    // Called once the component's DOM has been created, before it is inserted into the page.
+    fn mounted(&mut self, _props: &Self::Props, _scope: &Scope<Self>) {}
+
+    // Called when the parent renders the component with props unequal to the previous ones.
+    fn changed(&mut self, _old: &Self::Props, _props: &Self::Props) -> Cmd<Self::Message> {
+        Cmd::none()
+    }
+
+    fn destroyed(&mut self) {}
+}
+
+pub struct Scope<C: Component> {
+    state: Weak<State<C>>,
+    mailbox: Mailbox<C::Message>,
+}
+
+impl<C: Component> Scope<C> {
+    pub fn mailbox(&self) -> &Mailbox<C::Message> {
+        &self.mailbox
+    }
+
+    pub fn send(&self, message: C::Message) {
+        self.mailbox.send(message)
+    }
+
+    // Events are delivered once the component has finished handling the current message.
+    pub fn emit(&self, event: C::Event) {
+        if let Some(state) = self.state.upgrade() {
+            state.events.borrow_mut().push(event);
+            state.wake();
+        }
+    }
+}
+
+impl<C: Component> Clone for Scope<C> {
+    fn clone(&self) -> Self {
+        Scope {
+            state: self.state.clone(),
+            mailbox: self.mailbox.clone(),
+        }
+    }
+}
+
+type Emit = Rc<dyn Fn(Box<dyn Any>)>;
+
+type OnEvent<Message> = Rc<dyn Fn(Box<dyn Any>) -> Option<Message>>;
+
+pub struct VComponent<Message: 'static> {
+    type_id: TypeId,
+    component: Box<dyn AnyComponent>,
+    on_event: OnEvent<Message>,
+}
+
+impl<Message: 'static> VComponent<Message> {
+    // Embeds `C`, ignoring any events it emits.
+    pub fn new<C: Component>(props: C::Props) -> Self {
+        VComponent {
+            type_id: TypeId::of::<C>(),
+            component: Box::new(Embedded::<C> {
+                props: Some(props),
+                state: None,
+            }),
+            on_event: Rc::new(|_| None),
+        }
+    }
+
+    pub fn with_events<C: Component>(
+        props: C::Props,
+        on_event: impl Fn(C::Event) -> Message + 'static,
+    ) -> Self {
+        VComponent {
+            on_event: Rc::new(move |event| {
+                Some(on_event(*event.downcast::<C::Event>().ok().unwrap_throw()))
+            }),
+            ..VComponent::new::<C>(props)
+        }
+    }
+
+    pub fn create(&mut self, mailbox: &Mailbox<Message>) -> Node {
+        let emit = self.emit(mailbox);
+        self.component.create(emit, mailbox.schedule())
+    }
+
+    pub fn hydrate(
+        &mut self,
+        node: Option<Node>,
+        parent: &Node,
+        mailbox: &Mailbox<Message>,
+    ) -> Node {
+        let emit = self.emit(mailbox);
+        self.component
+            .hydrate(node, parent, emit, mailbox.schedule())
+    }
+
+    pub(crate) fn is_same(&self, other: &Self) -> bool {
+        self.type_id == other.type_id
+    }
+
+    pub fn patch(&mut self, old: &mut Self, mailbox: &Mailbox<Message>) -> Node {
+        let emit = self.emit(mailbox);
+        self.component.patch(&mut *old.component, emit)
+    }
+
+    pub fn node(&self) -> Option<Node> {
+        self.component.node()
+    }
+
+    pub(crate) fn nodes(&self, nodes: &mut Vec<Node>) {
+        self.component.nodes(nodes)
+    }
+
+    pub fn did_remove(&self) {
+        self.component.did_remove()
+    }
+
+    pub(crate) fn write_html(&self, html: &mut String) {
+        self.component.write_html(html)
+    }
+
+    pub(crate) fn do_map<NewMessage: 'static>(
+        self,
+        f: Rc<impl Fn(Message) -> NewMessage + 'static>,
+    ) -> VComponent<NewMessage> {
+        let on_event = self.on_event;
+        VComponent {
+            type_id: self.type_id,
+            component: self.component,
+            on_event: Rc::new(move |event| on_event(event).map(|message| f(message))),
+        }
+    }
+
+    fn emit(&self, mailbox: &Mailbox<Message>) -> Emit {
+        let on_event = self.on_event.clone();
+        let mailbox = mailbox.clone();
+        Rc::new(move |event| {
+            if let Some(message) = on_event(event) {
+                mailbox.send(message)
+            }
+        })
+    }
+}
+
+impl<Message> fmt::Debug for VComponent<Message> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VComponent")
+            .field("node", &self.node())
+            .finish()
+    }
+}
+
+// `VComponent` without the component's type, so that components of any type can be embedded in
+// the same tree.
+trait AnyComponent {
+    fn create(&mut self, emit: Emit, schedule: Option<Schedule>) -> Node;
+    fn hydrate(
+        &mut self,
+        node: Option<Node>,
+        parent: &Node,
+        emit: Emit,
+        schedule: Option<Schedule>,
+    ) -> Node;
+    fn patch(&mut self, old: &mut dyn AnyComponent, emit: Emit) -> Node;
+    fn node(&self) -> Option<Node>;
+    fn nodes(&self, nodes: &mut Vec<Node>);
+    fn did_remove(&self);
+    fn write_html(&self, html: &mut String);
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct Embedded<C: Component> {
+    // Moved into `state` once the component is mounted.
+    props: Option<C::Props>,
+    state: Option<Rc<State<C>>>,
+}
+
+impl<C: Component> AnyComponent for Embedded<C> {
+    fn create(&mut self, emit: Emit, schedule: Option<Schedule>) -> Node {
+        let state = State::new(self.props.take().unwrap_throw(), emit, schedule);
+        let mut vnode = state.view();
+        let node = vnode.create(&state.scope.mailbox);
+        self.state = Some(state.clone());
+        state.mount(vnode);
+        node
+    }
+
+    fn hydrate(
+        &mut self,
+        node: Option<Node>,
+        parent: &Node,
+        emit: Emit,
+        schedule: Option<Schedule>,
+    ) -> Node {
+        let state = State::new(self.props.take().unwrap_throw(), emit, schedule);
+        let mut vnode = state.view();
+        let node = vnode.hydrate(node, parent, &state.scope.mailbox);
+        self.state = Some(state.clone());
+        state.mount(vnode);
+        node
+    }
+
+    fn patch(&mut self, old: &mut dyn AnyComponent, emit: Emit) -> Node {
+        let old = old.as_any_mut().downcast_mut::<Self>().unwrap_throw();
+        let state = old.state.take().unwrap_throw();
+        state.patch(self.props.take().unwrap_throw(), emit);
+        self.state = Some(state);
+        self.node().unwrap_throw()
+    }
+
+    fn node(&self) -> Option<Node> {
+        self.state.as_ref()?.vnode.borrow().as_ref()?.node()
+    }
+
+    fn nodes(&self, nodes: &mut Vec<Node>) {
+        if let Some(ref state) = self.state {
+            if let Some(ref vnode) = *state.vnode.borrow() {
+                vnode.nodes(nodes);
+            }
+        }
+    }
+
+    fn did_remove(&self) {
+        if let Some(ref state) = self.state {
+            state.remove();
+        }
+    }
+
+    fn write_html(&self, html: &mut String) {
+        match self.state {
+            Some(ref state) => {
+                if let Some(ref vnode) = *state.vnode.borrow() {
+                    vnode.write_html(html);
+                }
+            }
+            None => {
+                let props = self.props.as_ref().unwrap_throw();
+                C::create(props).view(props).write_html(html);
+            }
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+struct State<C: Component> {
+    component: RefCell<C>,
+    props: RefCell<C::Props>,
+    vnode: RefCell<Option<VNode<C::Message>>>,
+    scope: Scope<C>,
+    stash: Stash,
+    queue: RefCell<VecDeque<C::Message>>,
+    events: RefCell<Vec<C::Event>>,
+    emit: RefCell<Emit>,
+    // Schedules renders with the application's scheduler. Without one, they happen right away.
+    schedule: Option<Schedule>,
+    is_busy: Cell<bool>,
+    is_dirty: Cell<bool>,
+    is_render_scheduled: Cell<bool>,
+    is_removed: Cell<bool>,
+}
+
+impl<C: Component> State<C> {
+    // The component stays busy, queueing messages, until it is mounted.
+    fn new(props: C::Props, emit: Emit, schedule: Option<Schedule>) -> Rc<Self> {
+        Rc::new_cyclic(|weak: &Weak<Self>| {
+            let stash = Stash::default();
+            let context = Context {
+                stash: stash.clone(),
+                flush: None,
+                delegation: None,
+                schedule: schedule.clone(),
+            };
+            let cloned = weak.clone();
+            let mailbox = Mailbox::with_context(
+                move |message| {
+                    if let Some(state) = cloned.upgrade() {
+                        state.send(message);
+                    }
+                },
+                context,
+            );
+            State {
+                component: RefCell::new(C::create(&props)),
+                props: RefCell::new(props),
+                vnode: RefCell::new(None),
+                scope: Scope {
+                    state: weak.clone(),
+                    mailbox,
+                },
+                stash,
+                queue: RefCell::new(VecDeque::new()),
+                events: RefCell::new(Vec::new()),
+                emit: RefCell::new(emit),
+                schedule,
+                is_busy: Cell::new(true),
+                is_dirty: Cell::new(false),
+                is_render_scheduled: Cell::new(false),
+                is_removed: Cell::new(false),
+            }
+        })
+    }
+
+    fn view(&self) -> VNode<C::Message> {
+        self.component.borrow().view(&self.props.borrow())
+    }
+
+    fn mount(&self, vnode: VNode<C::Message>) {
+        self.vnode.replace(Some(vnode));
+        self.component
+            .borrow_mut()
+            .mounted(&self.props.borrow(), &self.scope);
+        self.settle(false);
+    }
+
+    fn patch(&self, props: C::Props, emit: Emit) {
+        self.emit.replace(emit);
+        if props == *self.props.borrow() {
+            return;
+        }
+        let old = self.props.replace(props);
+        let is_busy = self.is_busy.replace(true);
+        let cmd = self
+            .component
+            .borrow_mut()
+            .changed(&old, &self.props.borrow());
+        self.run(cmd);
+        if !is_busy {
+            self.settle(false);
+        }
+    }
+
+    fn send(&self, message: C::Message) {
+        if self.is_removed.get() {
+            return;
+        }
+        self.queue.borrow_mut().push_back(message);
+        self.wake();
+    }
+
+    fn wake(&self) {
+        if !self.is_busy.replace(true) {
+            self.settle(true);
+        }
+    }
+
+    fn render(&self) {
+        self.is_render_scheduled.set(false);
+        if !self.is_busy.replace(true) {
+            self.settle(false);
+        }
+    }
+
+    fn run(&self, cmd: Cmd<C::Message>) {
+        if !cmd.skips_render() {
+            self.is_dirty.set(true);
+        }
+        cmd.run(&self.scope.mailbox);
+    }
+
+    // Handles queued messages and renders until there is nothing left to do, then delivers the
+    // events emitted meanwhile. The parent may render again in response, so nothing must be
+    // borrowed by then. If `defer`, rendering waits for the application's scheduler instead.
+    fn settle(&self, defer: bool) {
+        let defer = defer && self.schedule.is_some();
+        loop {
+            let message = self.queue.borrow_mut().pop_front();
+            if let Some(message) = message {
+                let cmd =
+                    self.component
+                        .borrow_mut()
+                        .update(&self.props.borrow(), message, &self.scope);
+                self.run(cmd);
+            } else if defer {
+                break;
+            } else if self.is_dirty.replace(false) && !self.is_removed.get() {
+                let mut vnode = self.view();
+                let mut old = self.vnode.borrow_mut();
+                vnode.patch(old.as_mut().unwrap_throw(), &self.scope.mailbox);
+                *old = Some(vnode);
+                drop(old);
+            } else {
+                break;
+            }
+        }
+        self.is_busy.set(false);
+        if self.is_dirty.get() && !self.is_removed.get() && !self.is_render_scheduled.replace(true)
+        {
+            let state = self.scope.state.clone();
+            (self.schedule.as_ref().unwrap_throw())(Box::new(move || {
+                if let Some(state) = state.upgrade() {
+                    state.render();
+                }
+            }));
+        }
+        let events = self.events.take();
+        if !events.is_empty() && !self.is_removed.get() {
+            let emit = self.emit.borrow().clone();
+            for event in events {
+                emit(Box::new(event));
+            }
+        }
+    }
+
+    fn remove(&self) {
+        self.is_removed.set(true);
+        if let Some(ref vnode) = *self.vnode.borrow() {
+            vnode.did_remove(&self.scope.mailbox);
+        }
+        self.component.borrow_mut().destroyed();
+        self.queue.borrow_mut().clear();
+        // Cancels timeouts and subscriptions started through the component's mailbox.
+        drop(self.stash.take());
+    }
+}
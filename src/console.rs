@@ -5,6 +5,8 @@ extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     pub fn log(str: &str);
     #[wasm_bindgen(js_namespace = console)]
+    pub fn warn(str: &str);
+    #[wasm_bindgen(js_namespace = console)]
     pub fn error(str: &str);
 }
 
@@ -0,0 +1,284 @@
+use crate::application::{self, Instance};
+use crate::dom::Node;
+use crate::{html as h, AppHandle, Application, Cmd, Mailbox, VNode};
+use std::cell::{Cell, RefCell};
+use std::fmt::{self, Debug};
+use std::rc::{Rc, Weak};
+use wasm_bindgen::UnwrapThrowExt;
+use web_sys as web;
+
+// Records every message sent to an application, before its middleware, so that its state can be
+// inspected at any point, and replayed. With the `serde` feature, the messages can be exported and imported too.
+pub struct Debugger<A: Application> {
+    inner: Rc<Inner<A>>,
+}
+
+struct Inner<A: Application> {
+    instance: Instance<A>,
+    // The model before the first recorded message.
+    initial: RefCell<A>,
+    entries: RefCell<Vec<Entry<A::Message>>>,
+    // The number of entries applied to the model currently shown.
+    index: Cell<usize>,
+    on_change: RefCell<Option<Box<dyn Fn()>>>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry<Message> {
+    // Milliseconds since the Unix epoch.
+    pub time: f64,
+    pub message: Message,
+}
+
+pub fn start<A>(app: A, node: web::Node) -> Debugger<A>
+where
+    A: Application + Clone,
+    A::Message: Clone + Debug,
+{
+    let instance = application::mount(app, Node::Web(node), false);
+    instance.start();
+    Debugger::attach(instance)
+}
+
+impl<A> Debugger<A>
+where
+    A: Application + Clone,
+    A::Message: Clone + Debug,
+{
+    pub(crate) fn attach(instance: Instance<A>) -> Self {
+        let initial = instance.app().borrow().clone();
+        let inner = Rc::new(Inner {
+            instance,
+            initial: RefCell::new(initial),
+            entries: RefCell::new(Vec::new()),
+            index: Cell::new(0),
+            on_change: RefCell::new(None),
+        });
+        let weak = Rc::downgrade(&inner);
+        inner.instance.record(move |message: &A::Message| {
+            if let Some(inner) = weak.upgrade() {
+                // A message sent while an earlier snapshot is shown discards the later ones.
+                let mut entries = inner.entries.borrow_mut();
+                entries.truncate(inner.index.get());
+                entries.push(Entry {
+                    time: now(),
+                    message: message.clone(),
+                });
+                inner.index.set(entries.len());
+                drop(entries);
+                inner.changed();
+            }
+        });
+        Debugger { inner }
+    }
+
+    pub fn app(&self) -> AppHandle<A> {
+        AppHandle::new(self.inner.instance.clone())
+    }
+
+    pub fn entries(&self) -> Vec<Entry<A::Message>> {
+        self.inner.entries.borrow().clone()
+    }
+
+    pub fn index(&self) -> usize {
+        self.inner.index.get()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Shows the model as it was after the first `index` messages, replayed through the
+    // middleware and `update_async`. Commands returned by `update` are not run again.
+    pub fn go_to(&self, index: usize) {
+        let index = index.min(self.len());
+        let app = self.snapshot(index);
+        self.inner.index.set(index);
+        self.inner.instance.replace_app(app);
+        self.inner.changed();
+    }
+
+    pub fn step_back(&self) {
+        self.go_to(self.index().saturating_sub(1));
+    }
+
+    pub fn step_forward(&self) {
+        self.go_to(self.index() + 1);
+    }
+
+    // Replays every recorded message against `app` instead of the original model.
+    pub fn replay(&self, app: A) {
+        self.inner.initial.replace(app);
+        self.go_to(self.len());
+    }
+
+    // The model after the first `index` messages, handled as the application handles them.
+    pub fn snapshot(&self, index: usize) -> A {
+        let app = self.inner.initial.borrow().clone();
+        let entries = self.inner.entries.borrow().clone();
+        let messages = entries.into_iter().take(index).map(|entry| entry.message);
+        Instance::replay(app, messages)
+    }
+
+    // The recorded messages as JSON, to be passed to `import`.
+    #[cfg(feature = "serde")]
+    pub fn export(&self) -> String
+    where
+        A::Message: serde::Serialize,
+    {
+        serde_json::to_string(&*self.inner.entries.borrow()).unwrap_throw()
+    }
+
+    // Replaces the recorded messages with exported ones, e.g. from a bug report, and shows the
+    // model after all of them. Call `replay` first to start from a different model.
+    #[cfg(feature = "serde")]
+    pub fn import(&self, json: &str) -> Result<(), serde_json::Error>
+    where
+        A::Message: serde::de::DeserializeOwned,
+    {
+        self.inner.entries.replace(serde_json::from_str(json)?);
+        self.go_to(self.len());
+        Ok(())
+    }
+
+    // Appends a panel to the page listing the recorded messages, with controls to step through
+    // them.
+    pub fn overlay(&self) {
+        let document = web::window().unwrap_throw().document().unwrap_throw();
+        let element = document.create_element("div").unwrap_throw();
+        document
+            .body()
+            .unwrap_throw()
+            .append_child(&element)
+            .unwrap_throw();
+        // The overlay only holds on to the debugger weakly, since the debugger holds the overlay.
+        let overlay = crate::start(
+            Overlay {
+                debugger: Rc::downgrade(&self.inner),
+            },
+            element.into(),
+        );
+        self.inner.on_change.replace(Some(Box::new(move || {
+            overlay.send(OverlayMessage::Refresh)
+        })));
+    }
+}
+
+impl<A: Application> Inner<A> {
+    fn changed(&self) {
+        if let Some(ref on_change) = *self.on_change.borrow() {
+            on_change();
+        }
+    }
+}
+
+impl<A: Application> Clone for Debugger<A> {
+    fn clone(&self) -> Self {
+        Debugger {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+impl<A: Application> fmt::Debug for Debugger<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Debugger")
+            .field("index", &self.inner.index.get())
+            .field("len", &self.inner.entries.borrow().len())
+            .finish()
+    }
+}
+
+struct Overlay<A: Application> {
+    debugger: Weak<Inner<A>>,
+}
+
+enum OverlayMessage {
+    Refresh,
+    GoTo(usize),
+    Log,
+}
+
+impl<A> Application for Overlay<A>
+where
+    A: Application + Clone,
+    A::Message: Clone + Debug,
+{
+    type Message = OverlayMessage;
+
+    fn update(&mut self, message: Self::Message, _: &Mailbox<Self::Message>) -> Cmd<Self::Message> {
+        let debugger = match self.debugger.upgrade() {
+            Some(inner) => Debugger { inner },
+            None => return Cmd::none(),
+        };
+        match message {
+            OverlayMessage::Refresh => {}
+            OverlayMessage::GoTo(index) => debugger.go_to(index),
+            OverlayMessage::Log => crate::console::log(&format!("{:#?}", debugger.entries())),
+        }
+        Cmd::none()
+    }
+
+    fn view(&self) -> VNode<Self::Message> {
+        let inner = match self.debugger.upgrade() {
+            Some(inner) => inner,
+            None => return h::div().into(),
+        };
+        let index = inner.index.get();
+        let entries = inner.entries.borrow();
+        h::div()
+            .attribute(
+                "style",
+                "position: fixed; right: 0; bottom: 0; z-index: 2147483647; max-height: 50%; \
+                 overflow: auto; padding: 0.5em; background: #222; color: #eee; \
+                 font: 12px monospace;",
+            )
+            .with((
+                h::div().with((
+                    h::button()
+                        .on("click", move |_| {
+                            OverlayMessage::GoTo(index.saturating_sub(1))
+                        })
+                        .with("◀"),
+                    format!(" {} / {} ", index, entries.len()),
+                    h::button()
+                        .on("click", move |_| OverlayMessage::GoTo(index + 1))
+                        .with("▶"),
+                    " ",
+                    h::button().on("click", |_| OverlayMessage::Log).with("Log"),
+                )),
+                h::ol().append(entries.iter().enumerate().map(|(i, entry)| {
+                    h::li()
+                        .attribute(
+                            "style",
+                            if i < index {
+                                "cursor: pointer"
+                            } else {
+                                "cursor: pointer; opacity: 0.5"
+                            },
+                        )
+                        .on("click", move |_| OverlayMessage::GoTo(i + 1))
+                        .with(format!("{:?}", entry.message))
+                })),
+            ))
+            .into()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64() * 1000.0)
+        .unwrap_or_default()
+}
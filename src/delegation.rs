@@ -0,0 +1,128 @@
+use crate::dom::{self, Event, Node};
+use crate::S;
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// One listener per event type on the element containing an application, dispatching to the
+// listeners in its views. See `Application::delegate_events`.
+pub(crate) struct Delegation {
+    container: Node,
+    dispatch: Rc<dyn Fn(Event)>,
+    listeners: RefCell<Vec<(S, Box<dyn Any>)>>,
+}
+
+impl Delegation {
+    pub(crate) fn new(container: Node, dispatch: impl Fn(Event) + 'static) -> Self {
+        Delegation {
+            container,
+            dispatch: Rc::new(dispatch),
+            listeners: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn container(&self) -> &Node {
+        &self.container
+    }
+
+    // Registers the root listener for `name` unless it already is. Returns `false` if events of
+    // this type do not bubble, so they must be listened to on each element instead.
+    pub(crate) fn listen(&self, name: &str) -> bool {
+        if !bubbles(name) {
+            return false;
+        }
+        let mut listeners = self.listeners.borrow_mut();
+        if !listeners.iter().any(|(n, _)| n == name) {
+            let listener =
+                dom::with(|dom| dom.add_listener(&self.container, name, self.dispatch.clone()));
+            listeners.push((name.to_string().into(), listener));
+        }
+        true
+    }
+
+    pub(crate) fn remove(&self) {
+        for (name, listener) in self.listeners.take() {
+            dom::with(|dom| dom.remove_listener(&self.container, &name, &*listener));
+        }
+    }
+}
+
+// The nodes from just below `container` down to the event's target, or `None` if the target is
+// not inside `container`.
+pub(crate) fn path(container: &Node, event: &Event) -> Option<Vec<Node>> {
+    let mut path = Vec::new();
+    let mut current = event.target();
+    dom::with(|dom| {
+        while let Some(node) = current {
+            if node == *container {
+                path.reverse();
+                return Some(path);
+            }
+            current = dom.parent(&node);
+            path.push(node);
+        }
+        None
+    })
+}
+
+// Events which bubble from the elements they are dispatched at. Anything else, including custom
+// events, is listened to on each element.
+fn bubbles(name: &str) -> bool {
+    matches!(
+        name,
+        "animationcancel"
+            | "animationend"
+            | "animationiteration"
+            | "animationstart"
+            | "auxclick"
+            | "beforeinput"
+            | "change"
+            | "click"
+            | "compositionend"
+            | "compositionstart"
+            | "compositionupdate"
+            | "contextmenu"
+            | "copy"
+            | "cut"
+            | "dblclick"
+            | "drag"
+            | "dragend"
+            | "dragenter"
+            | "dragleave"
+            | "dragover"
+            | "dragstart"
+            | "drop"
+            | "focusin"
+            | "focusout"
+            | "gotpointercapture"
+            | "input"
+            | "keydown"
+            | "keypress"
+            | "keyup"
+            | "lostpointercapture"
+            | "mousedown"
+            | "mousemove"
+            | "mouseout"
+            | "mouseover"
+            | "mouseup"
+            | "paste"
+            | "pointercancel"
+            | "pointerdown"
+            | "pointermove"
+            | "pointerout"
+            | "pointerover"
+            | "pointerup"
+            | "reset"
+            | "select"
+            | "submit"
+            | "touchcancel"
+            | "touchend"
+            | "touchmove"
+            | "touchstart"
+            | "transitioncancel"
+            | "transitionend"
+            | "transitionrun"
+            | "transitionstart"
+            | "wheel"
+    )
+}
@@ -0,0 +1,158 @@
+// Warnings about likely mistakes in views, checked during create and patch in debug builds only.
+// They are reported once the outermost create, hydrate or patch finishes, when the nodes they
+// point at are in place, so that each comes with the path of the element involved. Every
+// distinct warning is reported once, up to `REPORTED_LIMIT` of them, after which they start over.
+use crate::aspect::Aspect;
+use crate::dom::{self, Node};
+use crate::velement::Ns;
+use fxhash::FxHashSet as HashSet;
+use std::cell::{Cell, RefCell};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::rc::Rc;
+
+type Hook = Rc<dyn Fn(&str)>;
+
+const REPORTED_LIMIT: usize = 1024;
+
+thread_local! {
+    static PENDING: RefCell<Vec<(Node, String)>> = const { RefCell::new(Vec::new()) };
+    static REPORTED: RefCell<HashSet<String>> = RefCell::new(HashSet::default());
+    static HOOK: RefCell<Option<Hook>> = const { RefCell::new(None) };
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+// Receives every warning instead of the console.
+pub fn set_hook(f: impl Fn(&str) + 'static) {
+    HOOK.with(|hook| *hook.borrow_mut() = Some(Rc::new(f)));
+}
+
+// Reports the pending warnings. Called when the outermost create, hydrate or patch finishes.
+pub fn flush() {
+    let pending = PENDING.with(|pending| pending.take());
+    for (node, message) in pending {
+        let warning = format!("draco: {} at {}", message, path(&node));
+        let is_new = REPORTED.with(|reported| {
+            let mut reported = reported.borrow_mut();
+            if reported.len() >= REPORTED_LIMIT {
+                reported.clear();
+            }
+            reported.insert(warning.clone())
+        });
+        if is_new {
+            report(&warning);
+        }
+    }
+}
+
+// Held for the duration of a create, hydrate or patch.
+pub(crate) struct Pass;
+
+pub(crate) fn enter() -> Pass {
+    DEPTH.with(|depth| depth.set(depth.get() + 1));
+    Pass
+}
+
+impl Drop for Pass {
+    fn drop(&mut self) {
+        let depth = DEPTH.with(|depth| {
+            depth.set(depth.get() - 1);
+            depth.get()
+        });
+        if depth == 0 {
+            flush();
+        }
+    }
+}
+
+pub(crate) fn warn(node: &Node, message: impl FnOnce() -> String) {
+    if cfg!(debug_assertions) {
+        PENDING.with(|pending| pending.borrow_mut().push((node.clone(), message())));
+    }
+}
+
+pub(crate) fn check_keys<K: Eq + Hash + Debug>(parent: &Node, keys: impl Iterator<Item = K>) {
+    if cfg!(debug_assertions) {
+        let mut seen = HashSet::default();
+        for key in keys {
+            if let Some(key) = seen.replace(key) {
+                warn(parent, || {
+                    format!(
+                        "duplicate key {:?} among keyed children, which breaks reordering",
+                        key
+                    )
+                });
+            }
+        }
+    }
+}
+
+pub(crate) fn check_aspects<Message: 'static>(
+    name: &str,
+    aspects: &[Aspect<Message>],
+    element: &Node,
+) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    let mut listeners = HashSet::default();
+    for aspect in aspects {
+        match aspect {
+            Aspect::Listener(listener) => {
+                if !listeners.insert(listener.name()) {
+                    warn(element, || {
+                        format!("listener for {:?} registered twice", listener.name())
+                    });
+                }
+            }
+            Aspect::Attribute(attribute) => {
+                let is_form = matches!(name, "input" | "textarea" | "select" | "option");
+                if is_form && matches!(attribute.name(), "value" | "checked" | "selected") {
+                    warn(element, || {
+                        format!(
+                            "{:?} set as an attribute, which does not change the current value \
+                             once the user has edited it; set it as a property instead",
+                            attribute.name()
+                        )
+                    });
+                }
+            }
+            Aspect::Property(_) | Aspect::Style(_) | Aspect::Class(_) => {}
+        }
+    }
+}
+
+// The tag names from the root down to `node`, with the classes of HTML elements.
+fn path(node: &Node) -> String {
+    let mut parts = Vec::new();
+    let mut current = Some(node.clone());
+    dom::with(|dom| {
+        while let Some(node) = current {
+            let name = dom.node_name(&node).to_lowercase();
+            if name.starts_with('#') {
+                break;
+            }
+            let mut part = name;
+            if dom.namespace_uri(&node).as_deref() == Some(Ns::Html.uri()) {
+                for class in dom.class_name(&node).split_whitespace() {
+                    part.push('.');
+                    part.push_str(class);
+                }
+            }
+            parts.push(part);
+            current = dom.parent(&node);
+        }
+    });
+    parts.reverse();
+    parts.join(" > ")
+}
+
+// Passes `warning` to the hook, or else logs it to the console in the browser and drops it
+// elsewhere. Also used for warnings which are not limited to debug builds.
+pub(crate) fn report(warning: &str) {
+    match HOOK.with(|hook| hook.borrow().clone()) {
+        Some(hook) => hook(warning),
+        None if cfg!(target_arch = "wasm32") => crate::console::warn(warning),
+        None => {}
+    }
+}
@@ -0,0 +1,157 @@
+mod browser;
+pub mod memory;
+
+pub use self::browser::Browser;
+pub use self::memory::Memory;
+pub use crate::property::Value;
+pub use crate::velement::Ns;
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys as web;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Web(web::Node),
+    Memory(memory::Node),
+}
+
+#[derive(Clone, Debug)]
+pub enum Event {
+    Web(web::Event),
+    Memory(memory::Event),
+}
+
+pub trait Backend {
+    fn create_element(&self, ns: Ns, name: &str) -> Node;
+    fn create_text(&self, value: &str) -> Node;
+    // Inserting a fragment inserts its children, leaving it empty.
+    fn create_fragment(&self) -> Node;
+
+    fn node_name(&self, node: &Node) -> String;
+    fn namespace_uri(&self, node: &Node) -> Option<String>;
+    fn text(&self, node: &Node) -> Option<String>;
+    fn set_text(&self, node: &Node, value: &str);
+
+    // The first element in the document matching `selector`.
+    fn query_selector(&self, selector: &str) -> Option<Node>;
+    fn parent(&self, node: &Node) -> Option<Node>;
+    fn first_child(&self, node: &Node) -> Option<Node>;
+    fn next_sibling(&self, node: &Node) -> Option<Node>;
+    fn append_child(&self, parent: &Node, child: &Node);
+    fn insert_before(&self, parent: &Node, child: &Node, reference: Option<&Node>);
+    fn replace_child(&self, parent: &Node, child: &Node, old_child: &Node);
+    fn remove_child(&self, parent: &Node, child: &Node);
+    fn set_text_content(&self, node: &Node, value: &str);
+
+    // Includes `class` and `style`, as in the browser.
+    fn attribute_names(&self, element: &Node) -> Vec<String>;
+    fn set_attribute(&self, element: &Node, name: &str, value: &str);
+    fn remove_attribute(&self, element: &Node, name: &str);
+    fn get_property(&self, element: &Node, name: &str) -> Option<Value>;
+    fn set_property(&self, element: &Node, name: &str, value: &Value);
+    fn remove_property(&self, element: &Node, name: &str);
+    // Sets an inline style property, with the `important` priority if `important` is true.
+    fn set_style(&self, element: &Node, name: &str, value: &str, important: bool);
+    fn remove_style(&self, element: &Node, name: &str);
+    fn class_name(&self, element: &Node) -> String;
+    fn add_class(&self, element: &Node, name: &str);
+    fn remove_class(&self, element: &Node, name: &str);
+    fn set_class_name(&self, element: &Node, value: &str);
+
+    // The returned value must be passed back to `remove_listener` to detach `handler`.
+    fn add_listener(&self, element: &Node, name: &str, handler: Rc<dyn Fn(Event)>) -> Box<dyn Any>;
+    fn remove_listener(&self, element: &Node, name: &str, listener: &dyn Any);
+}
+
+thread_local! {
+    static BACKEND: RefCell<Rc<dyn Backend>> = RefCell::new(Rc::new(Browser));
+}
+
+// Sets the backend for the current thread. Every node passed to it must have been created by it:
+// the built-in backends panic on a node of the other kind, so switch back to `Browser` before
+// starting an application on the page after rendering with `Memory`.
+pub fn set_backend(backend: impl Backend + 'static) {
+    BACKEND.with(|current| *current.borrow_mut() = Rc::new(backend));
+}
+
+pub(crate) fn with<T>(f: impl FnOnce(&dyn Backend) -> T) -> T {
+    // Clone the backend out so that `f` can reenter, e.g. when a listener it triggers sends a
+    // message which leads to a render.
+    let backend = BACKEND.with(|current| current.borrow().clone());
+    f(&*backend)
+}
+
+impl Node {
+    pub fn as_web(&self) -> Option<&web::Node> {
+        match self {
+            Node::Web(node) => Some(node),
+            Node::Memory(_) => None,
+        }
+    }
+
+    pub fn as_memory(&self) -> Option<&memory::Node> {
+        match self {
+            Node::Web(_) => None,
+            Node::Memory(node) => Some(node),
+        }
+    }
+}
+
+impl From<web::Node> for Node {
+    fn from(node: web::Node) -> Self {
+        Node::Web(node)
+    }
+}
+
+impl From<memory::Node> for Node {
+    fn from(node: memory::Node) -> Self {
+        Node::Memory(node)
+    }
+}
+
+impl Event {
+    pub fn name(&self) -> String {
+        match self {
+            Event::Web(event) => event.type_(),
+            Event::Memory(event) => event.name().into(),
+        }
+    }
+
+    pub fn target(&self) -> Option<Node> {
+        match self {
+            Event::Web(event) => event.target()?.dyn_into::<web::Node>().ok().map(Node::Web),
+            Event::Memory(event) => event.target().map(Node::Memory),
+        }
+    }
+
+    pub fn prevent_default(&self) {
+        match self {
+            Event::Web(event) => event.prevent_default(),
+            Event::Memory(event) => event.prevent_default(),
+        }
+    }
+
+    pub fn stop_propagation(&self) {
+        match self {
+            Event::Web(event) => event.stop_propagation(),
+            Event::Memory(event) => event.stop_propagation(),
+        }
+    }
+
+    pub fn propagation_stopped(&self) -> bool {
+        match self {
+            Event::Web(event) => event.cancel_bubble(),
+            Event::Memory(event) => event.propagation_stopped(),
+        }
+    }
+
+    pub fn as_web(&self) -> Option<&web::Event> {
+        match self {
+            Event::Web(event) => Some(event),
+            Event::Memory(_) => None,
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use super::{Backend, Event, Node, Ns, Value};
+use std::any::Any;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::UnwrapThrowExt;
+use web_sys as web;
+
+#[derive(Debug, Default)]
+pub struct Browser;
+
+fn web_node(node: &Node) -> &web::Node {
+    node.as_web().expect_throw(
+        "draco: the browser backend was given an in-memory node; \
+         nodes must come from the backend set with `dom::set_backend`",
+    )
+}
+
+fn web_element(element: &Node) -> &web::Element {
+    web_node(element).unchecked_ref()
+}
+
+// HTML and SVG elements both have a `style`, but share no interface for it in `web_sys`.
+fn style(element: &Node) -> web::CssStyleDeclaration {
+    js_sys::Reflect::get(web_element(element), &JsValue::from_str("style"))
+        .unwrap_throw()
+        .unchecked_into()
+}
+
+fn document() -> web::Document {
+    web::window().unwrap_throw().document().unwrap_throw()
+}
+
+impl Backend for Browser {
+    fn create_element(&self, ns: Ns, name: &str) -> Node {
+        let element = match ns {
+            Ns::Html => document().create_element(wasm_bindgen::intern(name)),
+            Ns::Svg => document().create_element_ns(Some(ns.uri()), wasm_bindgen::intern(name)),
+        };
+        Node::Web(element.unwrap_throw().into())
+    }
+
+    fn create_text(&self, value: &str) -> Node {
+        Node::Web(document().create_text_node(value).into())
+    }
+
+    fn create_fragment(&self) -> Node {
+        Node::Web(document().create_document_fragment().into())
+    }
+
+    fn node_name(&self, node: &Node) -> String {
+        web_node(node).node_name()
+    }
+
+    fn namespace_uri(&self, node: &Node) -> Option<String> {
+        web_node(node).dyn_ref::<web::Element>()?.namespace_uri()
+    }
+
+    fn text(&self, node: &Node) -> Option<String> {
+        Some(web_node(node).dyn_ref::<web::Text>()?.data())
+    }
+
+    fn set_text(&self, node: &Node, value: &str) {
+        web_node(node).unchecked_ref::<web::Text>().set_data(value);
+    }
+
+    fn query_selector(&self, selector: &str) -> Option<Node> {
+        crate::select(selector).map(|element| Node::Web(element.into()))
+    }
+
+    fn parent(&self, node: &Node) -> Option<Node> {
+        web_node(node).parent_node().map(Node::Web)
+    }
+
+    fn first_child(&self, node: &Node) -> Option<Node> {
+        web_node(node).first_child().map(Node::Web)
+    }
+
+    fn next_sibling(&self, node: &Node) -> Option<Node> {
+        web_node(node).next_sibling().map(Node::Web)
+    }
+
+    fn append_child(&self, parent: &Node, child: &Node) {
+        web_node(parent)
+            .append_child(web_node(child))
+            .unwrap_throw();
+    }
+
+    fn insert_before(&self, parent: &Node, child: &Node, reference: Option<&Node>) {
+        web_node(parent)
+            .insert_before(web_node(child), reference.map(web_node))
+            .unwrap_throw();
+    }
+
+    fn replace_child(&self, parent: &Node, child: &Node, old_child: &Node) {
+        web_node(parent)
+            .replace_child(web_node(child), web_node(old_child))
+            .unwrap_throw();
+    }
+
+    fn remove_child(&self, parent: &Node, child: &Node) {
+        web_node(parent)
+            .remove_child(web_node(child))
+            .unwrap_throw();
+    }
+
+    fn set_text_content(&self, node: &Node, value: &str) {
+        web_node(node).set_text_content(Some(value));
+    }
+
+    fn attribute_names(&self, element: &Node) -> Vec<String> {
+        web_element(element)
+            .get_attribute_names()
+            .iter()
+            .filter_map(|name| name.as_string())
+            .collect()
+    }
+
+    fn set_attribute(&self, element: &Node, name: &str, value: &str) {
+        web_element(element)
+            .set_attribute(wasm_bindgen::intern(name), wasm_bindgen::intern(value))
+            .unwrap_throw();
+    }
+
+    fn remove_attribute(&self, element: &Node, name: &str) {
+        web_element(element).remove_attribute(name).unwrap_throw();
+    }
+
+    fn get_property(&self, element: &Node, name: &str) -> Option<Value> {
+        let value = js_sys::Reflect::get(web_element(element), &JsValue::from_str(name)).ok()?;
+        match value.as_bool() {
+            Some(bool) => Some(Value::Bool(bool)),
+            None => value.as_string().map(|string| Value::String(string.into())),
+        }
+    }
+
+    fn set_property(&self, element: &Node, name: &str, value: &Value) {
+        let value = match value {
+            Value::Bool(bool) => JsValue::from_bool(*bool),
+            Value::String(string) => JsValue::from_str(string),
+        };
+        js_sys::Reflect::set(web_element(element), &JsValue::from_str(name), &value).unwrap_throw();
+    }
+
+    fn remove_property(&self, element: &Node, name: &str) {
+        js_sys::Reflect::set(
+            web_element(element),
+            &JsValue::from_str(name),
+            &JsValue::UNDEFINED,
+        )
+        .unwrap_throw();
+    }
+
+    fn set_style(&self, element: &Node, name: &str, value: &str, important: bool) {
+        let priority = if important { "important" } else { "" };
+        style(element)
+            .set_property_with_priority(wasm_bindgen::intern(name), value, priority)
+            .unwrap_throw();
+    }
+
+    fn remove_style(&self, element: &Node, name: &str) {
+        style(element).remove_property(name).unwrap_throw();
+    }
+
+    fn class_name(&self, element: &Node) -> String {
+        web_element(element).class_name()
+    }
+
+    fn set_class_name(&self, element: &Node, value: &str) {
+        web_element(element).set_class_name(wasm_bindgen::intern(value));
+    }
+
+    fn add_class(&self, element: &Node, name: &str) {
+        web_element(element)
+            .class_list()
+            .add_1(wasm_bindgen::intern(name))
+            .unwrap_throw();
+    }
+
+    fn remove_class(&self, element: &Node, name: &str) {
+        web_element(element)
+            .class_list()
+            .remove_1(name)
+            .unwrap_throw();
+    }
+
+    fn add_listener(&self, element: &Node, name: &str, handler: Rc<dyn Fn(Event)>) -> Box<dyn Any> {
+        let closure = Closure::wrap(Box::new(move |event: web::Event| {
+            handler(Event::Web(event));
+        }) as Box<dyn Fn(web::Event) + 'static>);
+        (web_element(element).as_ref() as &web::EventTarget)
+            .add_event_listener_with_callback(name, closure.as_ref().unchecked_ref())
+            .unwrap_throw();
+        Box::new(closure)
+    }
+
+    fn remove_listener(&self, element: &Node, name: &str, listener: &dyn Any) {
+        let closure = listener
+            .downcast_ref::<Closure<dyn Fn(web::Event)>>()
+            .unwrap_throw();
+        (web_element(element).as_ref() as &web::EventTarget)
+            .remove_event_listener_with_callback(name, closure.as_ref().unchecked_ref())
+            .unwrap_throw();
+    }
+}
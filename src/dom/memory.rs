@@ -0,0 +1,663 @@
+use super::{Backend, Ns, Value};
+use crate::render;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::{Rc, Weak};
+use wasm_bindgen::UnwrapThrowExt;
+
+// A minimal DOM which lives entirely in Rust, for running applications outside the browser.
+#[derive(Debug, Default)]
+pub struct Memory;
+
+thread_local! {
+    static DOCUMENT: RefCell<Option<Node>> = const { RefCell::new(None) };
+}
+
+// Sets the element searched by `query_selector`, standing in for the document.
+pub fn set_document(node: &Node) {
+    DOCUMENT.with(|document| *document.borrow_mut() = Some(node.clone()));
+}
+
+#[derive(Clone)]
+pub struct Node(Rc<RefCell<Data>>);
+
+struct Data {
+    kind: Kind,
+    parent: Weak<RefCell<Data>>,
+    children: Vec<Node>,
+}
+
+enum Kind {
+    Element(Element),
+    Text(String),
+    Fragment,
+}
+
+type Handler = Rc<dyn Fn(super::Event)>;
+
+struct Element {
+    ns: Ns,
+    name: String,
+    class_name: String,
+    attributes: Vec<(String, String)>,
+    properties: Vec<(String, Value)>,
+    styles: Vec<(String, String, bool)>,
+    listeners: Vec<(String, Handler)>,
+}
+
+impl Element {
+    // The styles as the value of a `style` attribute.
+    fn css(&self) -> String {
+        let mut css = String::new();
+        for (name, value, important) in &self.styles {
+            render::declaration(&mut css, name, value, *important);
+        }
+        css
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Event(Rc<EventData>);
+
+#[derive(Debug)]
+struct EventData {
+    name: String,
+    target: RefCell<Option<Node>>,
+    default_prevented: Cell<bool>,
+    propagation_stopped: Cell<bool>,
+}
+
+impl Node {
+    fn new(kind: Kind) -> Self {
+        Node(Rc::new(RefCell::new(Data {
+            kind,
+            parent: Weak::new(),
+            children: Vec::new(),
+        })))
+    }
+
+    pub fn element(ns: Ns, name: &str) -> Self {
+        Node::new(Kind::Element(Element {
+            ns,
+            name: name.into(),
+            class_name: String::new(),
+            attributes: Vec::new(),
+            properties: Vec::new(),
+            styles: Vec::new(),
+            listeners: Vec::new(),
+        }))
+    }
+
+    pub fn text(value: &str) -> Self {
+        Node::new(Kind::Text(value.into()))
+    }
+
+    pub fn fragment() -> Self {
+        Node::new(Kind::Fragment)
+    }
+
+    pub fn name(&self) -> Option<String> {
+        match self.0.borrow().kind {
+            Kind::Element(ref element) => Some(element.name.clone()),
+            Kind::Text(_) | Kind::Fragment => None,
+        }
+    }
+
+    pub fn value(&self) -> Option<String> {
+        match self.0.borrow().kind {
+            Kind::Element(_) | Kind::Fragment => None,
+            Kind::Text(ref text) => Some(text.clone()),
+        }
+    }
+
+    pub fn text_content(&self) -> String {
+        match self.0.borrow().kind {
+            Kind::Element(_) | Kind::Fragment => {
+                self.children().iter().map(Node::text_content).collect()
+            }
+            Kind::Text(ref text) => text.clone(),
+        }
+    }
+
+    pub fn class_name(&self) -> String {
+        self.with_element(|element| element.class_name.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<String> {
+        self.with_element(|element| match name {
+            "class" => Some(element.class_name.clone()).filter(|class| !class.is_empty()),
+            "style" => Some(element.css()).filter(|css| !css.is_empty()),
+            _ => element
+                .attributes
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.clone()),
+        })?
+    }
+
+    // The value of an inline style property, followed by ` !important` if it has that priority.
+    pub fn style(&self, name: &str) -> Option<String> {
+        self.with_element(|element| {
+            element
+                .styles
+                .iter()
+                .find(|(n, _, _)| n == name)
+                .map(|(_, value, important)| match important {
+                    true => format!("{} !important", value),
+                    false => value.clone(),
+                })
+        })?
+    }
+
+    pub fn property(&self, name: &str) -> Option<Value> {
+        self.with_element(|element| {
+            element
+                .properties
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.clone())
+        })?
+    }
+
+    pub fn listeners(&self) -> Vec<String> {
+        self.with_element(|element| {
+            element
+                .listeners
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    pub fn parent(&self) -> Option<Node> {
+        self.0.borrow().parent.upgrade().map(Node)
+    }
+
+    pub fn children(&self) -> Vec<Node> {
+        self.0.borrow().children.clone()
+    }
+
+    // Runs the listeners for `event` on this node and then on each of its ancestors, like
+    // `EventTarget.dispatchEvent` does for bubbling events. Returns `false` if any listener called
+    // `prevent_default`.
+    pub fn dispatch(&self, event: &Event) -> bool {
+        event.0.target.replace(Some(self.clone()));
+        let mut current = Some(self.clone());
+        while let Some(node) = current {
+            let handlers = node
+                .with_element(|element| {
+                    element
+                        .listeners
+                        .iter()
+                        .filter(|(name, _)| *name == event.0.name)
+                        .map(|(_, handler)| handler.clone())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            for handler in handlers {
+                handler(super::Event::Memory(event.clone()));
+            }
+            if event.propagation_stopped() {
+                break;
+            }
+            current = node.parent();
+        }
+        !event.default_prevented()
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html);
+        html
+    }
+
+    fn write_html(&self, html: &mut String) {
+        let data = self.0.borrow();
+        let element = match data.kind {
+            Kind::Element(ref element) => element,
+            Kind::Text(ref text) => return render::text(html, text),
+            Kind::Fragment => {
+                for child in &data.children {
+                    child.write_html(html);
+                }
+                return;
+            }
+        };
+
+        html.push('<');
+        html.push_str(&element.name);
+        if !element.class_name.is_empty() {
+            render::attribute(html, "class", Some(&element.class_name));
+        }
+        for (name, value) in &element.attributes {
+            render::attribute(html, name, Some(value));
+        }
+        if !element.styles.is_empty() {
+            render::attribute(html, "style", Some(&element.css()));
+        }
+        let mut textarea_value = None;
+        for (name, value) in &element.properties {
+            match (&*element.name, &**name, value) {
+                ("textarea", "value", Value::String(value)) => textarea_value = Some(value),
+                _ => render::property(html, name, value),
+            }
+        }
+
+        match element.ns {
+            Ns::Html if render::is_void(&element.name) => html.push('>'),
+            Ns::Svg if data.children.is_empty() => html.push_str("/>"),
+            _ => {
+                html.push('>');
+                match textarea_value {
+                    Some(value) => render::text(html, value),
+                    None => {
+                        for child in &data.children {
+                            child.write_html(html);
+                        }
+                    }
+                }
+                html.push_str("</");
+                html.push_str(&element.name);
+                html.push('>');
+            }
+        }
+    }
+
+    fn with_element<T>(&self, f: impl FnOnce(&mut Element) -> T) -> Option<T> {
+        match self.0.borrow_mut().kind {
+            Kind::Element(ref mut element) => Some(f(element)),
+            Kind::Text(_) | Kind::Fragment => None,
+        }
+    }
+
+    fn index(&self, child: &Node) -> Option<usize> {
+        self.0.borrow().children.iter().position(|c| c == child)
+    }
+
+    fn detach(&self) {
+        let parent = self.0.borrow().parent.upgrade().map(Node);
+        if let Some(parent) = parent {
+            parent.0.borrow_mut().children.retain(|child| child != self);
+        }
+        self.0.borrow_mut().parent = Weak::new();
+    }
+
+    fn insert(&self, index: usize, child: &Node) {
+        // Like in the DOM, inserting a fragment moves its children instead.
+        let is_fragment = matches!(child.0.borrow().kind, Kind::Fragment);
+        if is_fragment {
+            for (offset, grandchild) in child.children().iter().enumerate() {
+                grandchild.detach();
+                self.insert(index + offset, grandchild);
+            }
+            return;
+        }
+        child.0.borrow_mut().parent = Rc::downgrade(&self.0);
+        self.0.borrow_mut().children.insert(index, child.clone());
+    }
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Node").field(&self.to_html()).finish()
+    }
+}
+
+impl Event {
+    pub fn new(name: impl Into<String>) -> Self {
+        Event(Rc::new(EventData {
+            name: name.into(),
+            target: RefCell::new(None),
+            default_prevented: Cell::new(false),
+            propagation_stopped: Cell::new(false),
+        }))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    pub fn target(&self) -> Option<Node> {
+        self.0.target.borrow().clone()
+    }
+
+    pub fn prevent_default(&self) {
+        self.0.default_prevented.set(true);
+    }
+
+    pub fn default_prevented(&self) -> bool {
+        self.0.default_prevented.get()
+    }
+
+    pub fn stop_propagation(&self) {
+        self.0.propagation_stopped.set(true);
+    }
+
+    pub fn propagation_stopped(&self) -> bool {
+        self.0.propagation_stopped.get()
+    }
+}
+
+fn memory_node(node: &super::Node) -> &Node {
+    node.as_memory().expect_throw(
+        "draco: the memory backend was given a browser node; \
+         nodes must come from the backend set with `dom::set_backend`",
+    )
+}
+
+// Supports compound selectors such as `p`, `#id`, `.a.b` and `div#id.a`, without combinators
+// or attributes.
+fn matches(node: &Node, selector: &str) -> bool {
+    let name = match node.name() {
+        Some(name) => name,
+        None => return false,
+    };
+    let tag = selector.split(['#', '.']).next().unwrap_or_default();
+    if !tag.is_empty() && tag != "*" && !tag.eq_ignore_ascii_case(&name) {
+        return false;
+    }
+    let mut rest = &selector[tag.len()..];
+    while let Some(kind) = rest.chars().next() {
+        let part = rest[1..].split(['#', '.']).next().unwrap_or_default();
+        let is_match = match kind {
+            '#' => node.attribute("id").as_deref() == Some(part),
+            _ => node
+                .class_name()
+                .split_whitespace()
+                .any(|class| class == part),
+        };
+        if part.is_empty() || !is_match {
+            return false;
+        }
+        rest = &rest[1 + part.len()..];
+    }
+    true
+}
+
+// Parses the declarations of a `style` attribute, e.g. `color: red; margin: 0 !important`.
+fn parse_styles(css: &str) -> Vec<(String, String, bool)> {
+    css.split(';')
+        .filter_map(|declaration| {
+            let (name, value) = declaration.split_once(':')?;
+            let value = value.trim();
+            let (value, important) = match value.strip_suffix("!important") {
+                Some(value) => (value.trim_end(), true),
+                None => (value, false),
+            };
+            Some((name.trim().into(), value.into(), important))
+        })
+        .collect()
+}
+
+fn element<T>(node: &super::Node, f: impl FnOnce(&mut Element) -> T) -> T {
+    memory_node(node)
+        .with_element(f)
+        .expect_throw("draco: expected an element")
+}
+
+impl Backend for Memory {
+    fn create_element(&self, ns: Ns, name: &str) -> super::Node {
+        Node::element(ns, name).into()
+    }
+
+    fn create_text(&self, value: &str) -> super::Node {
+        Node::text(value).into()
+    }
+
+    fn create_fragment(&self) -> super::Node {
+        Node::fragment().into()
+    }
+
+    fn node_name(&self, node: &super::Node) -> String {
+        match memory_node(node).0.borrow().kind {
+            Kind::Element(ref element) => match element.ns {
+                Ns::Html => element.name.to_uppercase(),
+                Ns::Svg => element.name.clone(),
+            },
+            Kind::Text(_) => "#text".into(),
+            Kind::Fragment => "#document-fragment".into(),
+        }
+    }
+
+    fn namespace_uri(&self, node: &super::Node) -> Option<String> {
+        memory_node(node).with_element(|element| element.ns.uri().into())
+    }
+
+    fn text(&self, node: &super::Node) -> Option<String> {
+        memory_node(node).value()
+    }
+
+    fn set_text(&self, node: &super::Node, value: &str) {
+        if let Kind::Text(ref mut text) = memory_node(node).0.borrow_mut().kind {
+            *text = value.into();
+        }
+    }
+
+    // Searches the element set with `set_document`.
+    fn query_selector(&self, selector: &str) -> Option<super::Node> {
+        let mut stack =
+            DOCUMENT.with(|document| document.borrow().clone().into_iter().collect::<Vec<_>>());
+        while let Some(node) = stack.pop() {
+            if matches(&node, selector) {
+                return Some(node.into());
+            }
+            stack.extend(node.children().into_iter().rev());
+        }
+        None
+    }
+
+    fn parent(&self, node: &super::Node) -> Option<super::Node> {
+        memory_node(node).parent().map(Into::into)
+    }
+
+    fn first_child(&self, node: &super::Node) -> Option<super::Node> {
+        memory_node(node)
+            .children()
+            .first()
+            .cloned()
+            .map(Into::into)
+    }
+
+    fn next_sibling(&self, node: &super::Node) -> Option<super::Node> {
+        let node = memory_node(node);
+        let parent = node.parent()?;
+        let index = parent.index(node)?;
+        parent.children().get(index + 1).cloned().map(Into::into)
+    }
+
+    fn append_child(&self, parent: &super::Node, child: &super::Node) {
+        let (parent, child) = (memory_node(parent), memory_node(child));
+        child.detach();
+        let index = parent.0.borrow().children.len();
+        parent.insert(index, child);
+    }
+
+    fn insert_before(
+        &self,
+        parent: &super::Node,
+        child: &super::Node,
+        reference: Option<&super::Node>,
+    ) {
+        let (parent, child) = (memory_node(parent), memory_node(child));
+        if reference.map(memory_node) == Some(child) {
+            return;
+        }
+        child.detach();
+        let index = match reference {
+            Some(reference) => parent.index(memory_node(reference)).unwrap_throw(),
+            None => parent.0.borrow().children.len(),
+        };
+        parent.insert(index, child);
+    }
+
+    fn replace_child(&self, parent: &super::Node, child: &super::Node, old_child: &super::Node) {
+        let (parent, child, old_child) = (
+            memory_node(parent),
+            memory_node(child),
+            memory_node(old_child),
+        );
+        if child == old_child {
+            return;
+        }
+        child.detach();
+        let index = parent.index(old_child).unwrap_throw();
+        old_child.detach();
+        parent.insert(index, child);
+    }
+
+    fn remove_child(&self, parent: &super::Node, child: &super::Node) {
+        let child = memory_node(child);
+        debug_assert!(child.parent().as_ref() == Some(memory_node(parent)));
+        child.detach();
+    }
+
+    fn set_text_content(&self, node: &super::Node, value: &str) {
+        let node = memory_node(node);
+        if let Kind::Text(ref mut text) = node.0.borrow_mut().kind {
+            *text = value.into();
+            return;
+        }
+        for child in node.children() {
+            child.detach();
+        }
+        if !value.is_empty() {
+            node.insert(0, &Node::text(value));
+        }
+    }
+
+    // The class and the styles are kept apart from the other attributes, so setting or
+    // removing them as attributes goes through `class_name` and `styles`.
+    fn set_attribute(&self, node: &super::Node, name: &str, value: &str) {
+        element(node, |element| match name {
+            "class" => element.class_name = value.into(),
+            "style" => element.styles = parse_styles(value),
+            _ => match element.attributes.iter_mut().find(|(n, _)| n == name) {
+                Some((_, v)) => *v = value.into(),
+                None => element.attributes.push((name.into(), value.into())),
+            },
+        })
+    }
+
+    fn attribute_names(&self, node: &super::Node) -> Vec<String> {
+        memory_node(node)
+            .with_element(|element| {
+                let class = Some("class").filter(|_| !element.class_name.is_empty());
+                let style = Some("style").filter(|_| !element.styles.is_empty());
+                class
+                    .into_iter()
+                    .chain(element.attributes.iter().map(|(name, _)| &**name))
+                    .chain(style)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn remove_attribute(&self, node: &super::Node, name: &str) {
+        element(node, |element| match name {
+            "class" => element.class_name.clear(),
+            "style" => element.styles.clear(),
+            _ => element.attributes.retain(|(n, _)| n != name),
+        })
+    }
+
+    fn get_property(&self, node: &super::Node, name: &str) -> Option<Value> {
+        memory_node(node).property(name)
+    }
+
+    fn set_property(&self, node: &super::Node, name: &str, value: &Value) {
+        element(node, |element| {
+            match element.properties.iter_mut().find(|(n, _)| n == name) {
+                Some((_, v)) => *v = value.clone(),
+                None => element.properties.push((name.into(), value.clone())),
+            }
+        })
+    }
+
+    fn remove_property(&self, node: &super::Node, name: &str) {
+        element(node, |element| {
+            element.properties.retain(|(n, _)| n != name)
+        })
+    }
+
+    fn add_class(&self, node: &super::Node, name: &str) {
+        element(node, |element| {
+            if !element
+                .class_name
+                .split_whitespace()
+                .any(|class| class == name)
+            {
+                if !element.class_name.is_empty() {
+                    element.class_name.push(' ');
+                }
+                element.class_name.push_str(name);
+            }
+        })
+    }
+
+    fn remove_class(&self, node: &super::Node, name: &str) {
+        element(node, |element| {
+            let classes = element.class_name.split_whitespace();
+            element.class_name = classes
+                .filter(|class| *class != name)
+                .collect::<Vec<_>>()
+                .join(" ");
+        })
+    }
+
+    fn set_style(&self, node: &super::Node, name: &str, value: &str, important: bool) {
+        element(node, |element| {
+            match element.styles.iter_mut().find(|(n, _, _)| n == name) {
+                Some((_, v, i)) => {
+                    *v = value.into();
+                    *i = important;
+                }
+                None => element.styles.push((name.into(), value.into(), important)),
+            }
+        })
+    }
+
+    fn remove_style(&self, node: &super::Node, name: &str) {
+        element(node, |element| element.styles.retain(|(n, _, _)| n != name))
+    }
+
+    fn class_name(&self, node: &super::Node) -> String {
+        memory_node(node).class_name()
+    }
+
+    fn set_class_name(&self, node: &super::Node, value: &str) {
+        element(node, |element| element.class_name = value.into())
+    }
+
+    fn add_listener(
+        &self,
+        node: &super::Node,
+        name: &str,
+        handler: Rc<dyn Fn(super::Event)>,
+    ) -> Box<dyn Any> {
+        element(node, |element| {
+            element.listeners.push((name.into(), handler.clone()))
+        });
+        Box::new(handler)
+    }
+
+    fn remove_listener(&self, node: &super::Node, name: &str, listener: &dyn Any) {
+        let listener = listener
+            .downcast_ref::<Rc<dyn Fn(super::Event)>>()
+            .unwrap_throw();
+        element(node, |element| {
+            element.listeners.retain(|(n, handler)| {
+                n != name || !std::ptr::addr_eq(Rc::as_ptr(handler), Rc::as_ptr(listener))
+            })
+        })
+    }
+}
@@ -0,0 +1,154 @@
+use crate::dom::{self, Event, Node};
+use crate::velement::{Children, NonKeyed, With};
+use crate::{Mailbox, VNode};
+use derivative::Derivative;
+use std::rc::Rc;
+use wasm_bindgen::UnwrapThrowExt;
+
+// Renders its children as siblings of its own position instead of inside an element. An empty
+// text node marks where they start, so that an empty fragment can still be patched in place.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct Fragment<Message: 'static> {
+    children: NonKeyed<Message>,
+    node: Option<Node>,
+}
+
+pub fn fragment<Message: 'static>() -> Fragment<Message> {
+    Fragment::new()
+}
+
+impl<Message: 'static> Fragment<Message> {
+    pub fn new() -> Self {
+        Fragment {
+            children: NonKeyed::new(),
+            node: None,
+        }
+    }
+
+    pub fn with<W: With<NonKeyed<Message>>>(mut self, with: W) -> Self {
+        with.with(&mut self.children);
+        self
+    }
+
+    pub fn push<N: Into<VNode<Message>>>(mut self, vnode: N) -> Self {
+        self.children.0.push(vnode.into());
+        self
+    }
+
+    pub fn append<N: Into<VNode<Message>>, I: IntoIterator<Item = N>>(mut self, i: I) -> Self {
+        self.children.0.extend(i.into_iter().map(Into::into));
+        self
+    }
+
+    // Returns a DOM fragment holding every node, which the caller inserts in one go.
+    pub fn create(&mut self, mailbox: &Mailbox<Message>) -> Node {
+        let (fragment, node) = dom::with(|dom| (dom.create_fragment(), dom.create_text("")));
+        dom::with(|dom| dom.append_child(&fragment, &node));
+        self.children.create(&fragment, mailbox);
+        self.node = Some(node);
+        fragment
+    }
+
+    pub fn hydrate(
+        &mut self,
+        node: Option<Node>,
+        parent: &Node,
+        mailbox: &Mailbox<Message>,
+    ) -> Node {
+        // The marker is not rendered on the server.
+        let marker = dom::with(|dom| dom.create_text(""));
+        dom::with(|dom| dom.insert_before(parent, &marker, node.as_ref()));
+        let mut next = node;
+        for child in &mut self.children.0 {
+            child.hydrate(next, parent, mailbox);
+            next = child.next_sibling();
+        }
+        self.node = Some(marker.clone());
+        marker
+    }
+
+    pub fn patch(&mut self, old: &mut Self, mailbox: &Mailbox<Message>) -> Node {
+        let node = old.node.clone().unwrap_throw();
+        let (new, old) = (&mut self.children.0, &mut old.children.0);
+
+        for (old, new) in old.iter_mut().zip(new.iter_mut()) {
+            new.patch(old, mailbox);
+        }
+
+        for old in old.iter().skip(new.len()) {
+            old.remove(mailbox);
+        }
+
+        if new.len() > old.len() {
+            let next_sibling = match old.len() {
+                0 => dom::with(|dom| dom.next_sibling(&node)),
+                len => new[len - 1].next_sibling(),
+            };
+            let fragment = dom::with(|dom| dom.create_fragment());
+            for new in new.iter_mut().skip(old.len()) {
+                let new_node = new.create(mailbox);
+                dom::with(|dom| dom.append_child(&fragment, &new_node));
+            }
+            dom::with(|dom| {
+                let parent = dom.parent(&node).unwrap_throw();
+                dom.insert_before(&parent, &fragment, next_sibling.as_ref());
+            });
+        }
+
+        self.node = Some(node.clone());
+        node
+    }
+
+    pub fn did_remove(&self, mailbox: &Mailbox<Message>) {
+        self.children.did_remove(mailbox);
+    }
+
+    pub fn node(&self) -> Option<Node> {
+        self.node.clone()
+    }
+
+    pub(crate) fn nodes(&self, nodes: &mut Vec<Node>) {
+        nodes.extend(self.node());
+        for child in &self.children.0 {
+            child.nodes(nodes);
+        }
+    }
+
+    pub(crate) fn delegate(
+        &self,
+        path: &[Node],
+        name: &str,
+        event: &Event,
+        messages: &mut Vec<Message>,
+    ) -> bool {
+        self.children.delegate(path, name, event, messages)
+    }
+
+    pub(crate) fn write_html(&self, html: &mut String) {
+        self.children.write_html(html);
+    }
+
+    pub(crate) fn do_map<NewMessage: 'static>(
+        self,
+        f: Rc<impl Fn(Message) -> NewMessage + 'static>,
+    ) -> Fragment<NewMessage> {
+        let children = NonKeyed(
+            self.children
+                .0
+                .into_iter()
+                .map(|child| child.do_map(f.clone()))
+                .collect(),
+        );
+        Fragment {
+            children,
+            node: self.node,
+        }
+    }
+}
+
+impl<Message: 'static> Default for Fragment<Message> {
+    fn default() -> Self {
+        Fragment::new()
+    }
+}
@@ -26,8 +26,8 @@ elements! {
     colgroup datalist dd del details dfn div dl dt em embed fieldset figcaption figure footer form
     h1 h2 h3 h4 h5 h6 header hr i iframe img input ins kbd label legend li main mark math menu
     menuitem meter nav object ol optgroup option output p param pre progress q rp rt ruby s samp
-    section select small source span strong sub summary sup table tbody td textarea tfoot th thead
-    time tr track u ul var video wbr
+    script section select small source span strong style sub summary sup table tbody td textarea
+    tfoot th thead time tr track u ul var video wbr
 }
 
 impl<C: Children> VElement<C>
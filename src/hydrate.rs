@@ -0,0 +1,43 @@
+use crate::diagnostics;
+use crate::dom::{self, Node};
+
+// The server separates adjacent text nodes with empty comments; they carry no content.
+pub(crate) fn skip_comments(mut node: Option<Node>) -> Option<Node> {
+    dom::with(|dom| {
+        while let Some(comment) = node.take_if(|node| dom.node_name(node) == "#comment") {
+            node = dom.next_sibling(&comment);
+        }
+        node
+    })
+}
+
+pub(crate) fn mismatch(expected: &str, found: Option<&Node>) {
+    diagnostics::report(&format!(
+        "draco: hydration mismatch: expected {}, found {}",
+        expected,
+        found.map_or("nothing".into(), |node| {
+            dom::with(|dom| dom.node_name(node)).to_lowercase()
+        })
+    ));
+}
+
+// Puts a freshly created `node` where `found` was, or at the end of `parent` if the server
+// rendered fewer nodes than expected.
+pub(crate) fn replace(parent: &Node, node: &Node, found: Option<&Node>) {
+    dom::with(|dom| match found {
+        Some(found) => dom.replace_child(parent, node, found),
+        None => dom.append_child(parent, node),
+    });
+}
+
+pub(crate) fn remove_rest(parent: &Node, mut node: Option<Node>) {
+    dom::with(|dom| {
+        while let Some(extra) = node {
+            node = dom.next_sibling(&extra);
+            if dom.node_name(&extra) != "#comment" {
+                mismatch("nothing", Some(&extra));
+            }
+            dom.remove_child(parent, &extra);
+        }
+    });
+}
@@ -0,0 +1,123 @@
+use crate::S;
+use std::any::Any;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+use wasm_bindgen::UnwrapThrowExt;
+
+// Identifies a child of a keyed element across renders. Any `Hash + Eq + Debug` value can be a
+// key. Integers are equal if their values are, whatever their types, and so are strings; keys of
+// other types are only equal to keys of the same type.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Key(Repr);
+
+// Integers, strings, `bool`s and `char`s are stored inline, so only other keys are boxed.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Repr {
+    Int(i128),
+    Str(S),
+    Bool(bool),
+    Char(char),
+    Other(Other),
+}
+
+#[derive(Clone)]
+struct Other(Rc<dyn AnyKey>);
+
+impl Key {
+    pub fn new<K: Hash + Eq + fmt::Debug + 'static>(key: K) -> Self {
+        // `Option` lets owned keys be moved out of the `dyn Any`.
+        let mut key = Some(key);
+        let any = &mut key as &mut dyn Any;
+        macro_rules! int {
+            (from $($ty:ty)*) => {
+                $(
+                    if let Some(Some(key)) = any.downcast_ref::<Option<$ty>>() {
+                        return Key(Repr::Int(i128::from(*key)));
+                    }
+                )*
+            };
+            (try_from $($ty:ty)*) => {
+                $(
+                    if let Some(Some(key)) = any.downcast_ref::<Option<$ty>>() {
+                        // A `u128` which does not fit is boxed instead.
+                        if let Ok(key) = i128::try_from(*key) {
+                            return Key(Repr::Int(key));
+                        }
+                    }
+                )*
+            };
+        }
+        macro_rules! take {
+            ($($ty:ty => $variant:ident,)*) => {
+                $(
+                    if let Some(key) = any.downcast_mut::<Option<$ty>>() {
+                        return Key(Repr::$variant(key.take().unwrap_throw().into()));
+                    }
+                )*
+            };
+        }
+        int! { from i8 i16 i32 i64 i128 u8 u16 u32 u64 }
+        int! { try_from isize usize u128 }
+        take! {
+            &'static str => Str,
+            String => Str,
+            S => Str,
+            bool => Bool,
+            char => Char,
+        }
+        if let Some(key) = any.downcast_mut::<Option<Key>>() {
+            return key.take().unwrap_throw();
+        }
+        Key(Repr::Other(Other(Rc::new(key.unwrap_throw()))))
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Repr::Int(key) => key.fmt(f),
+            Repr::Str(key) => key.fmt(f),
+            Repr::Bool(key) => key.fmt(f),
+            Repr::Char(key) => key.fmt(f),
+            Repr::Other(key) => key.0.fmt(f),
+        }
+    }
+}
+
+impl PartialEq for Other {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.key_eq(&*other.0)
+    }
+}
+
+impl Eq for Other {}
+
+impl Hash for Other {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Any::type_id(self.0.as_any()).hash(state);
+        self.0.key_hash(state);
+    }
+}
+
+// `Hash + Eq` without the type parameters, so that keys can be boxed.
+trait AnyKey: fmt::Debug {
+    fn key_eq(&self, other: &dyn AnyKey) -> bool;
+    fn key_hash(&self, state: &mut dyn Hasher);
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<K: Hash + Eq + fmt::Debug + 'static> AnyKey for K {
+    fn key_eq(&self, other: &dyn AnyKey) -> bool {
+        other.as_any().downcast_ref::<K>() == Some(self)
+    }
+
+    fn key_hash(&self, mut state: &mut dyn Hasher) {
+        Hash::hash(self, &mut state)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -1,16 +1,15 @@
+use crate::dom::{Event, Node};
 use crate::{Mailbox, VNode};
 use derivative::Derivative;
 use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 use wasm_bindgen::UnwrapThrowExt;
-use web_sys as web;
 
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
 pub struct Lazy<Message: 'static> {
     hash: u64,
     vnode: Option<Box<VNode<Message>>>,
-    node: Option<web::Node>,
     #[derivative(Debug = "ignore")]
     view: Box<dyn Fn() -> VNode<Message>>,
 }
@@ -24,7 +23,6 @@ impl<Message: 'static> Lazy<Message> {
         Lazy {
             hash,
             vnode: None,
-            node: None,
             view: Box::new(move || view(&t)),
         }
     }
@@ -41,35 +39,43 @@ impl<Message: 'static> Lazy<Message> {
         Lazy {
             hash,
             vnode: None,
-            node: None,
             view: Box::new(move || view(&t, &arg)),
         }
     }
 
-    pub fn create(&mut self, mailbox: &Mailbox<Message>) -> web::Node {
+    pub fn create(&mut self, mailbox: &Mailbox<Message>) -> Node {
         let mut vnode = (self.view)();
         let node = vnode.create(mailbox);
-        self.node = Some(node.clone());
         self.vnode = Some(Box::new(vnode));
         node
     }
 
-    pub fn patch(&mut self, old: &mut Self, mailbox: &Mailbox<Message>) -> web::Node {
+    pub fn hydrate(
+        &mut self,
+        node: Option<Node>,
+        parent: &Node,
+        mailbox: &Mailbox<Message>,
+    ) -> Node {
+        let mut vnode = (self.view)();
+        let node = vnode.hydrate(node, parent, mailbox);
+        self.vnode = Some(Box::new(vnode));
+        node
+    }
+
+    pub fn patch(&mut self, old: &mut Self, mailbox: &Mailbox<Message>) -> Node {
         let mut old_vnode = *old.vnode.take().unwrap_throw();
         let old_node = old_vnode.node().unwrap_throw();
         if self.hash == old.hash {
             self.vnode = Some(Box::new(old_vnode));
-            self.node = Some(old_node.clone());
             return old_node;
         }
         let mut vnode = (self.view)();
         let node = vnode.patch(&mut old_vnode, mailbox);
-        self.node = Some(node.clone());
         self.vnode = Some(Box::new(vnode));
         node
     }
 
-    pub fn did_create(&self, node: &web::Node, mailbox: &Mailbox<Message>) {
+    pub fn did_create(&self, node: &Node, mailbox: &Mailbox<Message>) {
         if let Some(ref vnode) = self.vnode {
             vnode.did_create(node, mailbox);
         };
@@ -90,7 +96,33 @@ impl<Message: 'static> Lazy<Message> {
         })
     }
 
-    pub fn node(&self) -> Option<web::Node> {
-        self.node.clone()
+    pub fn node(&self) -> Option<Node> {
+        self.vnode.as_ref()?.node()
+    }
+
+    pub(crate) fn nodes(&self, nodes: &mut Vec<Node>) {
+        if let Some(ref vnode) = self.vnode {
+            vnode.nodes(nodes);
+        }
+    }
+
+    pub(crate) fn delegate(
+        &self,
+        path: &[Node],
+        name: &str,
+        event: &Event,
+        messages: &mut Vec<Message>,
+    ) -> bool {
+        match self.vnode {
+            Some(ref vnode) => vnode.delegate(path, name, event, messages),
+            None => false,
+        }
+    }
+
+    pub(crate) fn write_html(&self, html: &mut String) {
+        match self.vnode {
+            Some(ref vnode) => vnode.write_html(html),
+            None => (self.view)().write_html(html),
+        }
     }
 }
@@ -3,27 +3,59 @@ pub mod console;
 mod application;
 mod aspect;
 mod attribute;
+#[cfg(panic = "unwind")]
+mod boundary;
+mod class;
+mod cmd;
+pub mod component;
+pub mod debug;
+mod delegation;
+pub mod diagnostics;
+pub mod dom;
+mod fragment;
 pub mod html;
+mod hydrate;
+mod key;
 mod lazy;
 mod listener;
 mod mailbox;
+#[cfg(feature = "serde")]
+pub mod persist;
+pub mod portal;
 mod property;
+mod render;
 pub mod router;
+mod scheduler;
+mod style;
 pub mod subscription;
 pub mod svg;
+pub mod task;
+pub mod testing;
 pub mod url;
 mod velement;
 mod vnode;
 mod vtext;
 
-pub use self::application::{start, Application};
+pub use self::application::{hydrate, start, AppHandle, Application, Middleware, MiddlewareChain};
 pub use self::aspect::Aspect;
 pub use self::attribute::Attribute;
+#[cfg(panic = "unwind")]
+pub use self::boundary::Boundary;
+pub use self::class::Class;
+pub use self::cmd::Cmd;
+pub use self::component::{Component, VComponent};
+pub use self::fragment::{fragment, Fragment};
+pub use self::key::Key;
 pub use self::lazy::Lazy;
 pub use self::listener::Listener;
 pub use self::mailbox::Mailbox;
+pub use self::portal::{portal, Portal};
 pub use self::property::Property;
-pub use self::subscription::{Subscription, Unsubscribe};
+pub use self::render::render_to_string;
+pub use self::scheduler::Scheduler;
+pub use self::style::Style;
+pub use self::subscription::{Subscription, Subscriptions, Unsubscribe};
+pub use self::task::Task;
 pub use self::velement::{h, s};
 pub use self::velement::{VElement, VKeyedElement, VNonKeyedElement};
 pub use self::vnode::VNode;
@@ -1,30 +1,38 @@
+use crate::dom::{self, Event, Node};
 use crate::{Mailbox, S};
 use derivative::Derivative;
+use std::any::Any;
+use std::cell::RefCell;
 use std::rc::Rc;
-use wasm_bindgen::prelude::*;
-use wasm_bindgen::JsCast;
-use wasm_bindgen::UnwrapThrowExt;
-use web_sys as web;
+
+type Handler = Rc<dyn Fn(Event)>;
 
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
 pub struct Listener<Message> {
     name: S,
     #[derivative(Debug = "ignore")]
-    handler: Rc<dyn Fn(web::Event) -> Option<Message>>,
+    handler: Rc<dyn Fn(Event) -> Option<Message>>,
     #[derivative(Debug = "ignore")]
-    closure: Option<Closure<dyn Fn(web::Event)>>,
+    attached: Option<Rc<Attached>>,
+    // Called by the application's root listener instead of one of its own.
+    is_delegated: bool,
+}
+
+// The closure registered with the element, shared by every render's `Listener` for as long as
+// the element has a listener with this name. It calls whichever handler was rendered last.
+struct Attached {
+    handler: Rc<RefCell<Handler>>,
+    closure: Box<dyn Any>,
 }
 
 impl<Message: 'static> Listener<Message> {
-    pub fn new(
-        name: impl Into<S>,
-        handler: impl Fn(web::Event) -> Option<Message> + 'static,
-    ) -> Self {
+    pub fn new(name: impl Into<S>, handler: impl Fn(Event) -> Option<Message> + 'static) -> Self {
         Listener {
             name: name.into(),
             handler: Rc::new(handler),
-            closure: None,
+            attached: None,
+            is_delegated: false,
         }
     }
 
@@ -39,35 +47,71 @@ impl<Message: 'static> Listener<Message> {
         let Listener {
             name,
             handler,
-            closure,
+            attached,
+            is_delegated,
         } = self;
         let handler = Rc::clone(&handler);
         let handler = Rc::new(move |event| handler(event).map(|message| f(message)));
         Listener {
             name,
             handler,
-            closure,
+            attached,
+            is_delegated,
         }
     }
 
-    pub fn attach(&mut self, element: &web::Element, mailbox: &Mailbox<Message>) {
+    // Takes over `old`'s closure if there is one, swapping in this listener's handler, and
+    // registers a new closure otherwise.
+    pub fn patch(
+        &mut self,
+        old_listener: Option<&Listener<Message>>,
+        element: &Node,
+        mailbox: &Mailbox<Message>,
+    ) {
+        if let Some(delegation) = mailbox.delegation() {
+            if delegation.listen(&self.name) {
+                if let Some(old_listener) = old_listener {
+                    old_listener.remove(element);
+                }
+                self.is_delegated = true;
+                return;
+            }
+        }
         let mailbox = mailbox.clone();
         let handler = Rc::clone(&self.handler);
-        let closure = Closure::wrap(Box::new(move |event: web::Event| {
+        let handler: Handler = Rc::new(move |event| {
             if let Some(message) = handler(event) {
                 mailbox.send(message)
             }
-        }) as Box<dyn Fn(web::Event) + 'static>);
-        (element.as_ref() as &web::EventTarget)
-            .add_event_listener_with_callback(&self.name, closure.as_ref().unchecked_ref())
-            .unwrap_throw();
-        self.closure = Some(closure);
+        });
+        if let Some(attached) = old_listener.and_then(|old| old.attached.clone()) {
+            attached.handler.replace(handler);
+            self.attached = Some(attached);
+            return;
+        }
+        let handler = Rc::new(RefCell::new(handler));
+        let cloned = handler.clone();
+        // The handler may be replaced while it runs, if the message it sends is handled and
+        // rendered synchronously.
+        let closure = Rc::new(move |event| {
+            let handler = cloned.borrow().clone();
+            handler(event)
+        });
+        let closure = dom::with(|dom| dom.add_listener(element, &self.name, closure));
+        self.attached = Some(Rc::new(Attached { handler, closure }));
     }
 
-    pub fn detach(&self, element: &web::Element) {
-        let closure = self.closure.as_ref().unwrap_throw();
-        (element.as_ref() as &web::EventTarget)
-            .remove_event_listener_with_callback(&self.name, closure.as_ref().unchecked_ref())
-            .unwrap_throw();
+    pub fn remove(&self, element: &Node) {
+        if let Some(ref attached) = self.attached {
+            dom::with(|dom| dom.remove_listener(element, &self.name, &*attached.closure));
+        }
+    }
+
+    pub(crate) fn delegate(&self, name: &str, event: &Event) -> Option<Message> {
+        if self.is_delegated && self.name == name {
+            (self.handler)(event.clone())
+        } else {
+            None
+        }
     }
 }
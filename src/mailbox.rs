@@ -1,3 +1,4 @@
+use crate::delegation::Delegation;
 use crate::{Subscription, Unsubscribe};
 use derivative::Derivative;
 use std::any::Any;
@@ -16,18 +17,37 @@ pub struct Mailbox<Message: 'static> {
     inner: Rc<Inner<Message>>,
 }
 
+pub(crate) type Stash = Rc<RefCell<Vec<Box<dyn Any>>>>;
+
+pub(crate) type Schedule = Rc<dyn Fn(Box<dyn FnOnce()>)>;
+
+// Shared by a mailbox and all mailboxes mapped from it, so that an application can drop
+// everything stashed through any of them when it is unmounted, flush its render, delegate the
+// listeners in its view, and render its components with its scheduler.
+#[derive(Clone, Default)]
+pub(crate) struct Context {
+    pub(crate) stash: Stash,
+    pub(crate) flush: Option<Rc<dyn Fn()>>,
+    pub(crate) delegation: Option<Rc<Delegation>>,
+    pub(crate) schedule: Option<Schedule>,
+}
+
 struct Inner<Message: 'static> {
     f: Box<dyn Fn(Message)>,
     // XXX: Is this a good idea?
-    stash: RefCell<Vec<Box<dyn Any>>>,
+    context: Context,
 }
 
 impl<Message: 'static> Mailbox<Message> {
     pub fn new(f: impl Fn(Message) + 'static) -> Self {
+        Mailbox::with_context(f, Context::default())
+    }
+
+    pub(crate) fn with_context(f: impl Fn(Message) + 'static, context: Context) -> Self {
         Mailbox {
             inner: Rc::new(Inner {
                 f: Box::new(f),
-                stash: RefCell::new(Vec::new()),
+                context,
             }),
         }
     }
@@ -36,20 +56,33 @@ impl<Message: 'static> Mailbox<Message> {
         (self.inner.f)(message)
     }
 
+    // Asks the application to render synchronously instead of waiting for its scheduler, e.g. for
+    // an input whose value must not lag behind. When called from `update`, the render happens
+    // right after the current batch of messages is processed.
+    pub fn flush(&self) {
+        if let Some(ref flush) = self.inner.context.flush {
+            flush()
+        }
+    }
+
     pub fn send_after(&self, timeout: i32, f: impl Fn() -> Message + 'static) {
         let cloned = self.clone();
         let closure = Closure::wrap(Box::new(move || {
             cloned.send(f());
         }) as Box<dyn FnMut()>);
-        web::window()
-            .unwrap_throw()
+        let window = web::window().unwrap_throw();
+        let id = window
             .set_timeout_with_callback_and_timeout_and_arguments_0(
                 closure.as_ref().unchecked_ref(),
                 timeout,
             )
             .unwrap_throw();
         // TODO: Drop the closure when it is first called.
-        self.stash(closure);
+        self.stash(Unsubscribe::new(move || {
+            // We need to move `closure` here so that it isn't dropped too early.
+            let _ = closure;
+            window.clear_timeout_with_handle(id);
+        }));
     }
 
     pub fn subscribe<S: Subscription + 'static>(
@@ -73,12 +106,8 @@ impl<Message: 'static> Mailbox<Message> {
         self,
         f: impl Fn(NewMessage) -> Message + 'static,
     ) -> Mailbox<NewMessage> {
-        Mailbox {
-            inner: Rc::new(Inner {
-                f: Box::new(move |message| (self.inner.f)(f(message))),
-                stash: RefCell::new(Vec::new()),
-            }),
-        }
+        let context = self.inner.context.clone();
+        Mailbox::with_context(move |message| (self.inner.f)(f(message)), context)
     }
 
     pub fn spawn<F>(&self, future: F, f: impl Fn(F::Output) -> Message + 'static)
@@ -91,8 +120,30 @@ impl<Message: 'static> Mailbox<Message> {
         });
     }
 
+    pub(crate) fn delegation(&self) -> Option<&Delegation> {
+        self.inner.context.delegation.as_deref()
+    }
+
+    pub(crate) fn schedule(&self) -> Option<Schedule> {
+        self.inner.context.schedule.clone()
+    }
+
+    // A mailbox sending to this one whose listeners are never delegated, for views rendered
+    // outside the element containing the application.
+    pub(crate) fn undelegated(&self) -> Self {
+        if self.inner.context.delegation.is_none() {
+            return self.clone();
+        }
+        let cloned = self.clone();
+        let context = Context {
+            delegation: None,
+            ..self.inner.context.clone()
+        };
+        Mailbox::with_context(move |message| cloned.send(message), context)
+    }
+
     fn stash(&self, t: impl Any) {
-        self.inner.stash.borrow_mut().push(Box::new(t));
+        self.inner.context.stash.borrow_mut().push(Box::new(t));
     }
 }
 
@@ -0,0 +1,89 @@
+use crate::application;
+use crate::diagnostics;
+use crate::dom::Node;
+use crate::{AppHandle, Application};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::UnwrapThrowExt;
+use web_sys as web;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Storage {
+    Local,
+    Session,
+}
+
+// An application whose model is saved to Web Storage after every change and restored by `start`.
+pub trait Persist: Application + Serialize + DeserializeOwned {
+    const KEY: &'static str;
+    // Bump this whenever the serialized form of the model changes incompatibly.
+    const VERSION: u32 = 0;
+
+    fn storage() -> Storage {
+        Storage::Local
+    }
+
+    // Converts a model saved with an older (or newer) `VERSION` to the current one. Returning
+    // `None` discards it.
+    fn migrate(_version: u32, _model: serde_json::Value) -> Option<serde_json::Value> {
+        None
+    }
+}
+
+// Starts `app`, or the model saved by a previous run if there is one.
+pub fn start<A: Persist>(app: A, node: web::Node) -> AppHandle<A> {
+    let app = load().unwrap_or(app);
+    let instance = application::mount(app, Node::Web(node), false);
+    instance.watch(save);
+    instance.start();
+    AppHandle::new(instance)
+}
+
+pub fn load<A: Persist>() -> Option<A> {
+    let json = storage::<A>()?.get_item(A::KEY).ok()??;
+    let app = decode(&json);
+    if app.is_none() {
+        diagnostics::report(&format!("draco: discarding saved state for {:?}", A::KEY));
+    }
+    app
+}
+
+pub fn save<A: Persist>(app: &A) {
+    if let Some(storage) = storage::<A>() {
+        // This fails if the storage quota is exceeded, in which case there is nothing to do.
+        let _ = storage.set_item(A::KEY, &encode(app));
+    }
+}
+
+pub fn clear<A: Persist>() {
+    if let Some(storage) = storage::<A>() {
+        storage.remove_item(A::KEY).unwrap_throw();
+    }
+}
+
+pub fn encode<A: Persist>(app: &A) -> String {
+    serde_json::json!({
+        "version": A::VERSION,
+        "model": app,
+    })
+    .to_string()
+}
+
+pub fn decode<A: Persist>(json: &str) -> Option<A> {
+    let mut value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let version = value.get("version")?.as_u64()? as u32;
+    let mut model = value.get_mut("model")?.take();
+    if version != A::VERSION {
+        model = A::migrate(version, model)?;
+    }
+    serde_json::from_value(model).ok()
+}
+
+fn storage<A: Persist>() -> Option<web::Storage> {
+    let window = web::window()?;
+    match A::storage() {
+        Storage::Local => window.local_storage(),
+        Storage::Session => window.session_storage(),
+    }
+    .ok()?
+}
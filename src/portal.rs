@@ -0,0 +1,177 @@
+use crate::dom::{self, Node};
+use crate::velement::{Children, NonKeyed, Ns, With};
+use crate::{diagnostics, Mailbox, VNode, S};
+use derivative::Derivative;
+use std::rc::Rc;
+use wasm_bindgen::UnwrapThrowExt;
+use web_sys as web;
+
+// Renders its children into a container appended to `target` instead of in place. In place, it
+// leaves an empty text node behind so that it can be patched and removed like any other node.
+// If a selector target matches nothing when the portal is created, the children are rendered into
+// a container which is not in the document, and a warning is reported through `diagnostics`.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct Portal<Message: 'static> {
+    target: Target,
+    children: NonKeyed<Message>,
+    container: Option<Node>,
+    node: Option<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target {
+    Node(Node),
+    Selector(S),
+}
+
+pub fn portal<Message: 'static>(target: impl Into<Target>) -> Portal<Message> {
+    Portal::new(target)
+}
+
+impl<Message: 'static> Portal<Message> {
+    pub fn new(target: impl Into<Target>) -> Self {
+        Portal {
+            target: target.into(),
+            children: NonKeyed::new(),
+            container: None,
+            node: None,
+        }
+    }
+
+    pub fn with<W: With<NonKeyed<Message>>>(mut self, with: W) -> Self {
+        with.with(&mut self.children);
+        self
+    }
+
+    pub fn push<N: Into<VNode<Message>>>(mut self, vnode: N) -> Self {
+        self.children.0.push(vnode.into());
+        self
+    }
+
+    pub fn append<N: Into<VNode<Message>>, I: IntoIterator<Item = N>>(mut self, i: I) -> Self {
+        self.children.0.extend(i.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn create(&mut self, mailbox: &Mailbox<Message>) -> Node {
+        let node = dom::with(|dom| dom.create_text(""));
+        self.mount(&node, mailbox);
+        self.node = Some(node.clone());
+        node
+    }
+
+    pub fn hydrate(
+        &mut self,
+        node: Option<Node>,
+        parent: &Node,
+        mailbox: &Mailbox<Message>,
+    ) -> Node {
+        // Nothing is rendered on the server, so the children are always created from scratch.
+        let new = self.create(mailbox);
+        dom::with(|dom| dom.insert_before(parent, &new, node.as_ref()));
+        new
+    }
+
+    pub fn patch(&mut self, old: &mut Self, mailbox: &Mailbox<Message>) -> Node {
+        let node = old.node.clone().unwrap_throw();
+        if self.target == old.target {
+            let container = old.container.clone().unwrap_throw();
+            self.children
+                .patch(&mut old.children, &container, &mailbox.undelegated());
+            self.container = Some(container);
+        } else {
+            old.did_remove(mailbox);
+            self.mount(&node, mailbox);
+        }
+        self.node = Some(node.clone());
+        node
+    }
+
+    pub fn did_remove(&self, mailbox: &Mailbox<Message>) {
+        self.children.did_remove(mailbox);
+        if let Some(ref container) = self.container {
+            dom::with(|dom| {
+                if let Some(parent) = dom.parent(container) {
+                    dom.remove_child(&parent, container);
+                }
+            });
+        }
+    }
+
+    pub fn node(&self) -> Option<Node> {
+        self.node.clone()
+    }
+
+    pub(crate) fn do_map<NewMessage: 'static>(
+        self,
+        f: Rc<impl Fn(Message) -> NewMessage + 'static>,
+    ) -> Portal<NewMessage> {
+        let Portal {
+            target,
+            children,
+            container,
+            node,
+        } = self;
+        let children = NonKeyed(
+            children
+                .0
+                .into_iter()
+                .map(|child| child.do_map(f.clone()))
+                .collect(),
+        );
+        Portal {
+            target,
+            children,
+            container,
+            node,
+        }
+    }
+
+    fn mount(&mut self, node: &Node, mailbox: &Mailbox<Message>) {
+        // The container is outside the application, out of reach of delegated listeners.
+        let mailbox = &mailbox.undelegated();
+        let container = dom::with(|dom| dom.create_element(Ns::Html, "div"));
+        self.children.create(&container, mailbox);
+        match self.target.resolve() {
+            Some(target) => dom::with(|dom| dom.append_child(&target, &container)),
+            None => diagnostics::warn(node, || {
+                format!("portal target {:?} not found", self.target)
+            }),
+        }
+        self.container = Some(container);
+    }
+}
+
+impl Target {
+    fn resolve(&self) -> Option<Node> {
+        match self {
+            Target::Node(node) => Some(node.clone()),
+            Target::Selector(selector) => dom::with(|dom| dom.query_selector(selector)),
+        }
+    }
+}
+
+impl From<Node> for Target {
+    fn from(node: Node) -> Self {
+        Target::Node(node)
+    }
+}
+
+impl From<web::Element> for Target {
+    fn from(element: web::Element) -> Self {
+        Target::Node(Node::Web(element.into()))
+    }
+}
+
+impl From<&'static str> for Target {
+    fn from(selector: &'static str) -> Self {
+        Target::Selector(selector.into())
+    }
+}
+
+impl From<String> for Target {
+    fn from(selector: String) -> Self {
+        Target::Selector(selector.into())
+    }
+}
@@ -1,7 +1,6 @@
-use crate::S;
+use crate::dom::{self, Node};
+use crate::{render, S};
 use std::borrow::Cow;
-use wasm_bindgen::{JsValue, UnwrapThrowExt};
-use web_sys as web;
 
 #[derive(Debug)]
 pub struct Property {
@@ -26,53 +25,36 @@ impl Property {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     String(S),
     Bool(bool),
 }
 
 impl Property {
-    pub fn patch(&self, old_property: Option<&Property>, element: &web::Element) {
-        let set = |name, value| js_sys::Reflect::set(element, &JsValue::from_str(name), &value);
-        let set_bool = |name, value| set(name, JsValue::from_bool(value));
-        let set_str = |name, value| set(name, JsValue::from_str(value));
-        let get = |name| js_sys::Reflect::get(element, &JsValue::from_str(name)).ok();
-        let get_bool = |name| get(name).and_then(|value| value.as_bool());
-        let get_string = |name| get(name).and_then(|value| value.as_string());
-        match (&*self.name, &self.value) {
-            ("checked", Value::Bool(new_checked)) => {
-                if let Some(old_checked) = get_bool("checked") {
-                    if old_checked != *new_checked {
-                        set_bool("checked", *new_checked).unwrap_throw();
-                    }
-                }
-            }
-            ("value", Value::String(new_string)) => {
-                if let Some(old_string) = get_string("value") {
-                    if &old_string != new_string {
-                        set_str("value", new_string).unwrap_throw();
-                    }
+    pub fn patch(&self, old_property: Option<&Property>, element: &Node) {
+        dom::with(|dom| match (&*self.name, &self.value) {
+            // The user may have changed these since the last render, so compare against the
+            // live values instead of the old ones.
+            ("checked", Value::Bool(_)) | ("value", Value::String(_)) => {
+                if dom.get_property(element, &self.name).as_ref() != Some(&self.value) {
+                    dom.set_property(element, &self.name, &self.value);
                 }
             }
             (name, value) => {
-                if Some(&self.value) != old_property.map(|p| &p.value) {
-                    match value {
-                        Value::Bool(bool) => {
-                            set_bool(name, *bool).unwrap_throw();
-                        }
-                        Value::String(string) => {
-                            set_str(name, string).unwrap_throw();
-                        }
-                    }
+                if Some(value) != old_property.map(|p| &p.value) {
+                    dom.set_property(element, name, value);
                 }
             }
-        }
+        })
+    }
+
+    pub fn remove(&self, element: &Node) {
+        dom::with(|dom| dom.remove_property(element, &self.name));
     }
 
-    pub fn remove(&self, element: &web::Element) {
-        js_sys::Reflect::set(element, &JsValue::from_str(&self.name), &JsValue::UNDEFINED)
-            .unwrap_throw();
+    pub(crate) fn write_html(&self, html: &mut String) {
+        render::property(html, &self.name, &self.value);
     }
 }
 
@@ -0,0 +1,102 @@
+use crate::property::Value;
+use crate::Application;
+
+pub fn render_to_string<A: Application>(app: &A) -> String {
+    app.view().to_html()
+}
+
+pub(crate) fn is_void(name: &str) -> bool {
+    matches!(
+        name,
+        "area"
+            | "base"
+            | "br"
+            | "col"
+            | "embed"
+            | "hr"
+            | "img"
+            | "input"
+            | "link"
+            | "meta"
+            | "param"
+            | "source"
+            | "track"
+            | "wbr"
+    )
+}
+
+// Elements whose contents are text which the browser does not unescape.
+pub(crate) fn is_raw_text(name: &str) -> bool {
+    matches!(name, "script" | "style")
+}
+
+// Writes the contents of `<name>` unescaped, only breaking up anything which would close it.
+pub(crate) fn raw_text(html: &mut String, name: &str, text: &str) {
+    let mut rest = text;
+    while let Some(index) = rest.find("</") {
+        let after = &rest[index + 2..];
+        html.push_str(&rest[..index]);
+        let closes = after
+            .get(..name.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(name));
+        html.push_str(if closes { "<\\/" } else { "</" });
+        rest = after;
+    }
+    html.push_str(rest);
+}
+
+pub(crate) fn text(html: &mut String, text: &str) {
+    for char in text.chars() {
+        match char {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            _ => html.push(char),
+        }
+    }
+}
+
+pub(crate) fn attribute(html: &mut String, name: &str, value: Option<&str>) {
+    html.push(' ');
+    html.push_str(name);
+    if let Some(value) = value {
+        html.push_str("=\"");
+        for char in value.chars() {
+            match char {
+                '&' => html.push_str("&amp;"),
+                '"' => html.push_str("&quot;"),
+                '<' => html.push_str("&lt;"),
+                '>' => html.push_str("&gt;"),
+                _ => html.push(char),
+            }
+        }
+        html.push('"');
+    }
+}
+
+// Appends `name: value;` to the contents of a `style` attribute.
+pub(crate) fn declaration(css: &mut String, name: &str, value: &str, important: bool) {
+    if !css.is_empty() {
+        css.push(' ');
+    }
+    css.push_str(name);
+    css.push_str(": ");
+    css.push_str(value);
+    if important {
+        css.push_str(" !important");
+    }
+    css.push(';');
+}
+
+pub(crate) fn property(html: &mut String, name: &str, value: &Value) {
+    let name = name.to_ascii_lowercase();
+    match (&*name, value) {
+        // These are enumerated attributes, not boolean ones.
+        ("contenteditable", Value::Bool(bool)) | ("spellcheck", Value::Bool(bool)) => {
+            attribute(html, &name, Some(if *bool { "true" } else { "false" }))
+        }
+        (_, Value::Bool(true)) => attribute(html, &name, None),
+        (_, Value::Bool(false)) => {}
+        (_, Value::String(string)) => attribute(html, &name, Some(string)),
+    }
+}
@@ -0,0 +1,30 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::UnwrapThrowExt;
+use web_sys as web;
+
+// When to render after a batch of messages has been processed. With `Microtask` and
+// `AnimationFrame`, all messages sent before the render runs are rendered together.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Scheduler {
+    #[default]
+    Sync,
+    Microtask,
+    AnimationFrame,
+}
+
+impl Scheduler {
+    pub(crate) fn schedule(self, f: impl FnOnce() + 'static) {
+        match self {
+            Scheduler::Sync => f(),
+            Scheduler::Microtask => wasm_bindgen_futures::spawn_local(async move { f() }),
+            Scheduler::AnimationFrame => {
+                let closure = Closure::once_into_js(move |_: f64| f());
+                web::window()
+                    .unwrap_throw()
+                    .request_animation_frame(closure.unchecked_ref())
+                    .unwrap_throw();
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::dom::{self, Node};
+use crate::{render, S};
+
+// A single inline CSS property, set with `CSSStyleDeclaration.setProperty` so that properties not
+// rendered by the view (e.g. set by animations) are left alone. A value ending in `!important` is
+// set with that priority.
+#[derive(Debug, PartialEq)]
+pub struct Style {
+    name: S,
+    value: S,
+    important: bool,
+}
+
+impl Style {
+    pub fn new(name: impl Into<S>, value: impl Into<S>) -> Self {
+        let value = value.into();
+        match value.trim_end().strip_suffix("!important") {
+            Some(rest) => Self {
+                name: name.into(),
+                value: rest.trim_end().to_string().into(),
+                important: true,
+            },
+            None => Self {
+                name: name.into(),
+                value,
+                important: false,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn important(&self) -> bool {
+        self.important
+    }
+
+    pub fn patch(&self, old_style: Option<&Style>, element: &Node) {
+        if Some(self) != old_style {
+            dom::with(|dom| dom.set_style(element, &self.name, &self.value, self.important));
+        }
+    }
+
+    pub fn remove(&self, element: &Node) {
+        dom::with(|dom| dom.remove_style(element, &self.name));
+    }
+
+    pub(crate) fn write_css(&self, css: &mut String) {
+        render::declaration(css, &self.name, &self.value, self.important);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::S;
 use derivative::Derivative;
 use js_sys as js;
 use std::cell::{Cell, RefCell};
@@ -33,6 +34,77 @@ impl Drop for Unsubscribe {
     }
 }
 
+type Start<Message> = Box<dyn FnOnce(Send<Message>) -> Unsubscribe>;
+
+// A set of subscriptions identified by key. The runtime starts a subscription when its key first
+// appears and unsubscribes once the key is gone, so the key should change whenever the
+// subscription's configuration does.
+#[derive(Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct Subscriptions<Message> {
+    entries: Vec<(S, Start<Message>)>,
+}
+
+impl<Message: 'static> Subscriptions<Message> {
+    pub fn new() -> Self {
+        Subscriptions {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn add<Sub: Subscription + 'static>(
+        mut self,
+        key: impl Into<S>,
+        subscription: Sub,
+        f: impl Fn(Sub::Message) -> Message + 'static,
+    ) -> Self
+    where
+        Sub::Message: 'static,
+    {
+        self.entries.push((
+            key.into(),
+            Box::new(move |send: Send<Message>| {
+                subscription.subscribe(Rc::new(move |message| send(f(message))))
+            }),
+        ));
+        self
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(key, _)| &**key)
+    }
+
+    pub fn map<NewMessage: 'static>(
+        self,
+        f: impl Fn(Message) -> NewMessage + 'static,
+    ) -> Subscriptions<NewMessage> {
+        let f = Rc::new(f);
+        Subscriptions {
+            entries: self
+                .entries
+                .into_iter()
+                .map(|(key, start)| {
+                    let f = f.clone();
+                    let start: Start<NewMessage> = Box::new(move |send: Send<NewMessage>| {
+                        start(Rc::new(move |message| send(f(message))))
+                    });
+                    (key, start)
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn into_entries(self) -> Vec<(S, Start<Message>)> {
+        self.entries
+    }
+}
+
+impl<Message: 'static> std::fmt::Debug for Subscriptions<Message> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_list().entries(self.keys()).finish()
+    }
+}
+
 #[derive(Debug)]
 pub struct OnWindow {
     name: String,
@@ -97,7 +169,7 @@ impl Subscription for Interval {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct AnimationFrame {}
 
 impl AnimationFrame {
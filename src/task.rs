@@ -0,0 +1,153 @@
+use crate::application::Instance;
+use crate::{Application, Mailbox, S};
+use std::cell::{Cell, RefCell, RefMut};
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+pub(crate) type BoxFuture = Pin<Box<dyn Future<Output = ()>>>;
+
+// An asynchronous message handler, returned by `Application::update_async`.
+pub struct Task {
+    pub(crate) future: BoxFuture,
+    pub(crate) key: Option<S>,
+}
+
+impl Task {
+    pub fn new(future: impl Future<Output = ()> + 'static) -> Self {
+        Task {
+            future: Box::pin(future),
+            key: None,
+        }
+    }
+
+    // Starting another task with the same key cancels this one if it is still running.
+    pub fn key(mut self, key: impl Into<S>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+}
+
+// Access to the model from a `Task`.
+pub struct Model<A: Application> {
+    instance: Instance<A>,
+    token: Rc<Token>,
+}
+
+impl<A: Application> Model<A> {
+    pub(crate) fn new(instance: Instance<A>, token: Rc<Token>) -> Self {
+        Model { instance, token }
+    }
+
+    // Borrows the model, waiting for any other task holding it to drop its guard. Messages sent
+    // meanwhile are queued, and the application renders once the guard is dropped, so do not
+    // hold it across an `await` which waits for long. Returns `None` if the task has been
+    // cancelled or the application unmounted.
+    pub async fn lock(&self) -> Option<ModelGuard<'_, A>> {
+        std::future::poll_fn(|cx| {
+            if self.is_cancelled() {
+                return Poll::Ready(None);
+            }
+            match self.instance.lock() {
+                Some(app) => Poll::Ready(Some(ModelGuard {
+                    app: Some(app),
+                    instance: &self.instance,
+                })),
+                None => {
+                    self.instance.wait(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled() || self.instance.is_unmounted()
+    }
+
+    pub fn mailbox(&self) -> Mailbox<A::Message> {
+        self.instance.mailbox()
+    }
+}
+
+pub struct ModelGuard<'a, A: Application> {
+    // Always `Some` until dropped.
+    app: Option<RefMut<'a, A>>,
+    instance: &'a Instance<A>,
+}
+
+impl<A: Application> Deref for ModelGuard<'_, A> {
+    type Target = A;
+
+    fn deref(&self) -> &A {
+        self.app.as_ref().unwrap()
+    }
+}
+
+impl<A: Application> DerefMut for ModelGuard<'_, A> {
+    fn deref_mut(&mut self) -> &mut A {
+        self.app.as_mut().unwrap()
+    }
+}
+
+impl<A: Application> Drop for ModelGuard<'_, A> {
+    fn drop(&mut self) {
+        drop(self.app.take());
+        self.instance.unlock();
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Token {
+    is_cancelled: Cell<bool>,
+    is_finished: Cell<bool>,
+    waker: RefCell<Option<Waker>>,
+}
+
+impl Token {
+    pub(crate) fn cancel(&self) {
+        self.is_cancelled.set(true);
+        // Wake the task so that it is dropped without waiting for what it awaits.
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.is_cancelled.get()
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        self.is_finished.get()
+    }
+}
+
+// Runs `future` until it completes or `token` is cancelled.
+pub(crate) fn cancellable(mut future: BoxFuture, token: Rc<Token>) -> BoxFuture {
+    Box::pin(std::future::poll_fn(move |cx| {
+        if token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        token.waker.replace(Some(cx.waker().clone()));
+        let poll = future.as_mut().poll(cx);
+        if poll.is_ready() {
+            token.is_finished.set(true);
+        }
+        poll
+    }))
+}
+
+// Polls each of `futures` once, returning the ones which are still pending.
+pub(crate) fn poll(futures: Vec<BoxFuture>) -> Vec<BoxFuture> {
+    let mut cx = Context::from_waker(Waker::noop());
+    futures
+        .into_iter()
+        .filter_map(|mut future| match future.as_mut().poll(&mut cx) {
+            Poll::Ready(()) => None,
+            Poll::Pending => Some(future),
+        })
+        .collect()
+}
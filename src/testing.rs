@@ -0,0 +1,197 @@
+use crate::application::{self, Instance};
+use crate::debug::Debugger;
+use crate::dom::{self, memory, Backend, Memory, Ns, Value};
+use crate::{Application, Cmd, Scheduler};
+use std::cell::{Ref, RefCell};
+use std::fmt::Debug;
+use std::rc::Rc;
+use wasm_bindgen::UnwrapThrowExt;
+
+// Mounts an application on the in-memory DOM so that it can be driven from native tests.
+pub struct Harness<A: Application> {
+    instance: Instance<A>,
+    // Nodes only hold weak references to their parents, so keep the root alive here.
+    body: memory::Node,
+    messages: Rc<RefCell<Vec<A::Message>>>,
+}
+
+impl<A: Application> Harness<A>
+where
+    A::Message: Clone,
+{
+    pub fn new(app: A) -> Self {
+        Self::with_scheduler(app, Scheduler::Sync)
+    }
+
+    // Renders with `scheduler` instead of synchronously. Scheduled renders run when `tick` or
+    // `frame` is called.
+    pub fn with_scheduler(app: A, scheduler: Scheduler) -> Self {
+        dom::set_backend(Memory);
+        let body = memory::Node::element(Ns::Html, "body");
+        // Selectors, e.g. those of portals, are looked up in the body.
+        memory::set_document(&body);
+        let node = dom::Node::from(memory::Node::text(""));
+        Memory.append_child(&body.clone().into(), &node);
+        let instance = application::mount(app, node, true);
+        instance.set_scheduler(scheduler);
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        {
+            let messages = messages.clone();
+            instance
+                .observe(move |message: &A::Message| messages.borrow_mut().push(message.clone()));
+        }
+        instance.start();
+        Harness {
+            instance,
+            body,
+            messages,
+        }
+    }
+
+    pub fn app(&self) -> Ref<'_, A> {
+        self.instance.app().borrow()
+    }
+
+    pub fn root(&self) -> memory::Node {
+        self.instance.node().as_memory().unwrap_throw().clone()
+    }
+
+    pub fn body(&self) -> &memory::Node {
+        &self.body
+    }
+
+    pub fn html(&self) -> String {
+        self.root().to_html()
+    }
+
+    // Every message processed by `update` so far, in order.
+    pub fn messages(&self) -> Vec<A::Message> {
+        self.messages.borrow().clone()
+    }
+
+    // The commands returned by `update` since the last call, excluding `Cmd::None`.
+    pub fn commands(&self) -> Vec<Cmd<A::Message>> {
+        self.instance.take_commands()
+    }
+
+    // The keys of the subscriptions currently returned by `Application::subscriptions`. They are
+    // not started here.
+    pub fn subscriptions(&self) -> Vec<String> {
+        self.instance.subscription_keys()
+    }
+
+    pub fn unmount(&self) {
+        self.instance.unmount();
+    }
+
+    pub fn send(&self, message: A::Message) {
+        self.instance.send(message);
+    }
+
+    // Processes any queued messages and renders.
+    pub fn drain(&self) {
+        if !self.instance.is_idle() {
+            self.instance.update();
+        }
+    }
+
+    // Polls the tasks started by `Application::update_async` once, which runs each of them until
+    // it awaits something that is not ready.
+    pub fn poll(&self) {
+        self.instance.poll_tasks();
+        self.drain();
+    }
+
+    // Runs the pending microtasks, i.e. the renders scheduled with `Scheduler::Microtask`.
+    pub fn tick(&self) {
+        self.instance.run_scheduled(Scheduler::Microtask);
+    }
+
+    // Runs the pending microtasks and then the next animation frame.
+    pub fn frame(&self) {
+        self.tick();
+        self.instance.run_scheduled(Scheduler::AnimationFrame);
+    }
+
+    pub fn find(&self, f: impl Fn(&memory::Node) -> bool) -> Option<memory::Node> {
+        self.find_all(f).into_iter().next()
+    }
+
+    pub fn find_all(&self, f: impl Fn(&memory::Node) -> bool) -> Vec<memory::Node> {
+        let mut found = Vec::new();
+        let mut stack = vec![self.root()];
+        while let Some(node) = stack.pop() {
+            if node.name().is_some() && f(&node) {
+                found.push(node.clone());
+            }
+            stack.extend(node.children().into_iter().rev());
+        }
+        found
+    }
+
+    pub fn by_tag(&self, name: &str) -> Vec<memory::Node> {
+        self.find_all(|node| node.name().as_deref() == Some(name))
+    }
+
+    pub fn by_class(&self, name: &str) -> Vec<memory::Node> {
+        self.find_all(|node| {
+            node.class_name()
+                .split_whitespace()
+                .any(|class| class == name)
+        })
+    }
+
+    pub fn by_id(&self, id: &str) -> Option<memory::Node> {
+        self.find(|node| node.attribute("id").as_deref() == Some(id))
+    }
+
+    // Elements having a text child containing `text`.
+    pub fn by_text(&self, text: &str) -> Vec<memory::Node> {
+        self.find_all(|node| {
+            node.children()
+                .iter()
+                .any(|child| child.value().is_some_and(|value| value.contains(text)))
+        })
+    }
+
+    // Dispatches an event named `name` at `node` and processes the resulting messages. Returns
+    // `false` if a listener called `prevent_default`.
+    pub fn dispatch(&self, node: &memory::Node, name: &str) -> bool {
+        let not_prevented = node.dispatch(&memory::Event::new(name));
+        self.drain();
+        not_prevented
+    }
+
+    pub fn click(&self, node: &memory::Node) -> bool {
+        self.dispatch(node, "click")
+    }
+
+    pub fn input(&self, node: &memory::Node, value: &str) -> bool {
+        Memory.set_property(
+            &node.clone().into(),
+            "value",
+            &Value::String(value.to_string().into()),
+        );
+        self.dispatch(node, "input")
+    }
+
+    pub fn check(&self, node: &memory::Node, checked: bool) -> bool {
+        Memory.set_property(&node.clone().into(), "checked", &Value::Bool(checked));
+        self.dispatch(node, "input")
+    }
+
+    pub fn submit(&self, node: &memory::Node) -> bool {
+        self.dispatch(node, "submit")
+    }
+}
+
+impl<A> Harness<A>
+where
+    A: Application + Clone,
+    A::Message: Clone + Debug,
+{
+    // Starts recording messages from the current state of the application.
+    pub fn debugger(&self) -> Debugger<A> {
+        Debugger::attach(self.instance.clone())
+    }
+}
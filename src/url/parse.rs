@@ -12,11 +12,11 @@ pub trait Parse: Sized {
     }
 }
 
-impl<'a> Parse for &'a str {
+impl Parse for &str {
     type Output = ();
 
     fn parse(&self, url: &Url, index: usize) -> Option<(Self::Output, usize)> {
-        if url.path.get(index).map_or(false, |string| string == self) {
+        if url.path.get(index).is_some_and(|string| string == self) {
             Some(((), index + 1))
         } else {
             None
@@ -47,7 +47,7 @@ impl<T: FromStr> Parse for Param<T> {
 #[derive(Debug)]
 pub struct Query<'a, T: FromStr>(&'a str, PhantomData<T>);
 
-pub fn query<T: FromStr>(name: &str) -> Query<T> {
+pub fn query<T: FromStr>(name: &str) -> Query<'_, T> {
     Query(name, PhantomData)
 }
 
@@ -167,6 +167,6 @@ impl<'a, T> Parser<'a, T> {
     }
 }
 
-pub fn parse<T>(url: &Url) -> Parser<T> {
+pub fn parse<T>(url: &Url) -> Parser<'_, T> {
     Parser::new(url)
 }
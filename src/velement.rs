@@ -1,15 +1,21 @@
-use crate::{aspect, property, Aspect, Attribute, Listener, Mailbox, Property, VNode, S};
+use crate::dom::{self, Event, Node};
+use crate::{
+    aspect, diagnostics, hydrate, property, render, Aspect, Attribute, Class, Key, Listener,
+    Mailbox, Property, Style, VNode, S,
+};
 // use std::collections::HashMap;
 use derivative::Derivative;
 use fxhash::FxHashMap as HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
 use std::rc::Rc;
 use wasm_bindgen::UnwrapThrowExt;
-use wasm_bindgen::{JsCast, JsValue};
-use web_sys as web;
 
 pub type VNonKeyedElement<Message> = VElement<NonKeyed<Message>>;
 pub type VKeyedElement<Message> = VElement<Keyed<Message>>;
 
+type Ref<Message> = Box<dyn Fn(Option<Node>) -> Message>;
+
 #[derive(Derivative)]
 #[derivative(Debug(bound = "C: std::fmt::Debug"))]
 pub struct VElement<C: Children> {
@@ -19,23 +25,32 @@ pub struct VElement<C: Children> {
     aspects: Vec<Aspect<C::Message>>,
     children: C,
     #[derivative(Debug = "ignore")]
-    ref_: Option<Box<dyn Fn(Option<web::Element>) -> C::Message>>,
-    node: Option<web::Element>,
+    ref_: Option<Ref<C::Message>>,
+    node: Option<Node>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Ns {
     Html,
     Svg,
 }
 
+impl Ns {
+    pub fn uri(self) -> &'static str {
+        match self {
+            Ns::Html => "http://www.w3.org/1999/xhtml",
+            Ns::Svg => "http://www.w3.org/2000/svg",
+        }
+    }
+}
+
 #[derive(Default, Derivative)]
 #[derivative(Debug(bound = ""))]
-pub struct Keyed<Message: 'static>(Vec<(u64, VNode<Message>)>);
+pub struct Keyed<Message: 'static>(Vec<(Key, VNode<Message>)>);
 
 #[derive(Default, Derivative)]
 #[derivative(Debug(bound = ""))]
-pub struct NonKeyed<Message: 'static>(Vec<VNode<Message>>);
+pub struct NonKeyed<Message: 'static>(pub(crate) Vec<VNode<Message>>);
 
 pub fn h<Message: 'static>(name: &'static str) -> VNonKeyedElement<Message> {
     VElement::new(Ns::Html, name)
@@ -63,9 +78,34 @@ where
 
     pub fn class(mut self, value: impl Into<S>) -> Self {
         self.class = value.into();
+        let class = &self.class;
+        self.aspects.retain(|aspect| match aspect {
+            Aspect::Class(token) => !has_token(class, token.name()),
+            _ => true,
+        });
         self
     }
 
+    // Adds class tokens which are diffed one by one, unlike `class`. Tokens which `class` already
+    // contains are skipped, so that they stay when the token is no longer added.
+    pub fn classes<N: Into<S>>(mut self, names: impl IntoIterator<Item = N>) -> Self {
+        for name in names {
+            let name = name.into();
+            if !has_token(&self.class, &name) {
+                self.aspects.push(Class::new(name).into());
+            }
+        }
+        self
+    }
+
+    pub fn class_if(self, name: impl Into<S>, condition: bool) -> Self {
+        if condition {
+            self.classes(Some(name))
+        } else {
+            self
+        }
+    }
+
     pub fn attribute(mut self, name: impl Into<S>, value: impl Into<S>) -> Self {
         self.aspects.push(Attribute::new(name, value).into());
         self
@@ -76,10 +116,15 @@ where
         self
     }
 
+    pub fn style(mut self, name: impl Into<S>, value: impl Into<S>) -> Self {
+        self.aspects.push(Style::new(name, value).into());
+        self
+    }
+
     pub fn on<N: Into<S>>(
         mut self,
         name: N,
-        handler: impl Fn(web::Event) -> C::Message + 'static,
+        handler: impl Fn(Event) -> C::Message + 'static,
     ) -> Self {
         self.aspects
             .push(Listener::new(name, move |event| Some(handler(event))).into());
@@ -89,7 +134,7 @@ where
     pub fn on_<N: Into<S>>(
         mut self,
         name: N,
-        handler: impl Fn(web::Event) -> Option<C::Message> + 'static,
+        handler: impl Fn(Event) -> Option<C::Message> + 'static,
     ) -> Self {
         self.aspects.push(Listener::new(name, handler).into());
         self
@@ -97,71 +142,104 @@ where
 
     pub fn on_input(self, handler: impl Fn(String) -> C::Message + 'static) -> Self {
         self.on_("input", move |event| {
-            Some(handler(
-                js_sys::Reflect::get(&&event.target()?, &JsValue::from_str("value"))
-                    .ok()?
-                    .as_string()?,
-            ))
+            match dom::with(|dom| dom.get_property(&event.target()?, "value"))? {
+                property::Value::String(value) => Some(handler(value.into())),
+                property::Value::Bool(_) => None,
+            }
         })
     }
 
     pub fn on_checked(self, handler: impl Fn(bool) -> C::Message + 'static) -> Self {
         self.on_("input", move |event| {
-            Some(handler(
-                js_sys::Reflect::get(&&event.target()?, &JsValue::from_str("checked"))
-                    .ok()?
-                    .as_bool()?,
-            ))
+            match dom::with(|dom| dom.get_property(&event.target()?, "checked"))? {
+                property::Value::Bool(checked) => Some(handler(checked)),
+                property::Value::String(_) => None,
+            }
         })
     }
 
-    pub fn ref_(mut self, handler: impl Fn(Option<web::Element>) -> C::Message + 'static) -> Self {
+    // Called with the element once it is created, and with `None` once it is removed.
+    pub fn ref_(mut self, handler: impl Fn(Option<Node>) -> C::Message + 'static) -> Self {
         self.ref_ = Some(Box::new(handler));
         self
     }
 
-    pub fn create(&mut self, mailbox: &Mailbox<C::Message>) -> web::Element {
-        let document = web::window().unwrap_throw().document().unwrap_throw();
+    pub fn create(&mut self, mailbox: &Mailbox<C::Message>) -> Node {
+        let element = dom::with(|dom| dom.create_element(self.ns, self.name));
 
-        let element = match self.ns {
-            Ns::Html => document
-                .create_element(wasm_bindgen::intern(self.name))
-                .unwrap_throw(),
-            Ns::Svg => document
-                .create_element_ns(
-                    Some("http://www.w3.org/2000/svg"),
-                    wasm_bindgen::intern(self.name),
-                )
-                .unwrap_throw(),
-        };
-
-        self.children
-            .create(element.as_ref() as &web::Node, mailbox);
+        self.children.create(&element, mailbox);
 
         aspect::patch(&mut self.aspects, &[], &element, mailbox);
+        diagnostics::check_aspects(self.name, &self.aspects, &element);
 
+        // Overrides any `class` attribute.
         if !self.class.is_empty() {
-            element.set_class_name(wasm_bindgen::intern(&self.class));
+            dom::with(|dom| dom.set_class_name(&element, &self.class_name()));
         }
 
         self.node = Some(element.clone());
 
-        self.did_create(element.as_ref(), mailbox);
+        self.did_create(&element, mailbox);
 
         element
     }
 
-    pub fn patch(&mut self, old: &mut Self, mailbox: &Mailbox<C::Message>) -> web::Element {
+    pub fn hydrate(
+        &mut self,
+        node: Option<Node>,
+        parent: &Node,
+        mailbox: &Mailbox<C::Message>,
+    ) -> Node {
+        let element = match node {
+            Some(node)
+                if dom::with(|dom| {
+                    dom.node_name(&node).eq_ignore_ascii_case(self.name)
+                        && dom.namespace_uri(&node).as_deref() == Some(self.ns.uri())
+                }) =>
+            {
+                node
+            }
+            _ => {
+                hydrate::mismatch(&format!("<{}>", self.name), node.as_ref());
+                let element = self.create(mailbox);
+                hydrate::replace(parent, &element, node.as_ref());
+                return element;
+            }
+        };
+
+        self.children.hydrate(&element, mailbox);
+
+        self.remove_server_attributes(&element);
+        aspect::patch(&mut self.aspects, &[], &element, mailbox);
+        diagnostics::check_aspects(self.name, &self.aspects, &element);
+
+        let class_name = self.class_name();
+        dom::with(|dom| {
+            if dom.class_name(&element) != class_name {
+                dom.set_class_name(&element, &class_name);
+            }
+        });
+
+        self.node = Some(element.clone());
+
+        self.did_create(&element, mailbox);
+
+        element
+    }
+
+    pub fn patch(&mut self, old: &mut Self, mailbox: &Mailbox<C::Message>) -> Node {
         debug_assert!(self.name == old.name);
         let old_element = old.node.clone().unwrap_throw();
 
         self.children
-            .patch(&mut old.children, old_element.as_ref(), mailbox);
+            .patch(&mut old.children, &old_element, mailbox);
 
         aspect::patch(&mut self.aspects, &old.aspects, &old_element, mailbox);
+        diagnostics::check_aspects(self.name, &self.aspects, &old_element);
 
+        // Replacing `className` drops every token, so set the ones from `classes` with it.
         if self.class != old.class {
-            old_element.set_class_name(wasm_bindgen::intern(&self.class));
+            dom::with(|dom| dom.set_class_name(&old_element, &self.class_name()));
         }
 
         self.node = Some(old_element.clone());
@@ -169,24 +247,174 @@ where
         old_element
     }
 
-    pub fn did_create(&self, node: &web::Node, mailbox: &Mailbox<C::Message>) {
+    pub(crate) fn delegate(
+        &self,
+        path: &[Node],
+        name: &str,
+        event: &Event,
+        messages: &mut Vec<C::Message>,
+    ) -> bool {
+        if path.first() != self.node.as_ref() {
+            return false;
+        }
+        self.children.delegate(&path[1..], name, event, messages);
+        // Like the browser, run every listener on the element which stops propagation, but none
+        // on its ancestors.
+        if !event.propagation_stopped() {
+            for aspect in &self.aspects {
+                if let Aspect::Listener(listener) = aspect {
+                    messages.extend(listener.delegate(name, event));
+                }
+            }
+        }
+        true
+    }
+
+    // `class` followed by the tokens from `classes`.
+    fn class_name(&self) -> S {
+        let mut class_name = self.class.clone();
+        for aspect in &self.aspects {
+            if let Aspect::Class(class) = aspect {
+                let class_name = class_name.to_mut();
+                if !class_name.is_empty() {
+                    class_name.push(' ');
+                }
+                class_name.push_str(class.name());
+            }
+        }
+        class_name
+    }
+
+    // Removes the attributes rendered by the server which this element does not have, before
+    // hydrate sets the ones it does.
+    fn remove_server_attributes(&self, element: &Node) {
+        dom::with(|dom| {
+            for name in dom.attribute_names(element) {
+                let is_class = name == "class" && !self.class.is_empty();
+                let is_known = is_class
+                    || self.aspects.iter().any(|aspect| match aspect {
+                        Aspect::Attribute(attribute) => {
+                            attribute.name().eq_ignore_ascii_case(&name)
+                        }
+                        Aspect::Property(property) => property.name().eq_ignore_ascii_case(&name),
+                        Aspect::Style(_) => name == "style",
+                        Aspect::Class(_) => name == "class",
+                        Aspect::Listener(_) => false,
+                    });
+                if !is_known {
+                    dom.remove_attribute(element, &name);
+                }
+            }
+        });
+    }
+
+    pub(crate) fn has_ref(&self) -> bool {
+        self.ref_.is_some()
+    }
+
+    pub fn did_create(&self, node: &Node, mailbox: &Mailbox<C::Message>) {
         if let Some(ref ref_) = self.ref_ {
-            mailbox.send(ref_(Some(
-                node.dyn_ref::<web::Element>().unwrap_throw().clone(),
-            )));
+            mailbox.send(ref_(Some(node.clone())));
         }
     }
 
     pub fn did_remove(&self, mailbox: &Mailbox<C::Message>) {
-        if let Some(ref ref_) = self.ref_ {
+        self.children.did_remove(mailbox);
+        if let (Some(ref ref_), Some(_)) = (&self.ref_, &self.node) {
             mailbox.send(ref_(None));
         }
     }
 
-    pub fn node(&self) -> Option<web::Element> {
+    pub fn node(&self) -> Option<Node> {
         self.node.clone()
     }
 
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html);
+        html
+    }
+
+    pub(crate) fn write_html(&self, html: &mut String) {
+        html.push('<');
+        html.push_str(self.name);
+
+        // A `class` attribute and the classes are written as one attribute, with `class`
+        // overriding the attribute as it does in the DOM.
+        let mut class_name = self.class_name();
+        let mut attribute = self.aspects.iter().filter_map(|aspect| match aspect {
+            Aspect::Attribute(attribute) if attribute.name() == "class" => Some(attribute.value()),
+            _ => None,
+        });
+        if let Some(value) = attribute.next_back().map(str::trim) {
+            if self.class.is_empty() && !value.is_empty() {
+                let class_name = class_name.to_mut();
+                if !class_name.is_empty() {
+                    class_name.insert(0, ' ');
+                }
+                class_name.insert_str(0, value);
+            }
+        }
+        if !class_name.is_empty() {
+            render::attribute(html, "class", Some(&class_name));
+        }
+
+        // A `<textarea>`'s value is its text content.
+        let mut textarea_value = None;
+        // A `style` attribute and the styles are written as one attribute.
+        let mut css = String::new();
+
+        for aspect in &self.aspects {
+            match aspect {
+                Aspect::Attribute(attribute) if attribute.name() == "style" => {
+                    let value = attribute.value().trim();
+                    if !value.is_empty() {
+                        if !css.is_empty() {
+                            css.push(' ');
+                        }
+                        css.push_str(value);
+                        if !value.ends_with(';') {
+                            css.push(';');
+                        }
+                    }
+                }
+                Aspect::Attribute(attribute) if attribute.name() == "class" => {}
+                Aspect::Attribute(attribute) => attribute.write_html(html),
+                Aspect::Property(property) => match (self.name, property.value()) {
+                    ("textarea", property::Value::String(value)) if property.name() == "value" => {
+                        textarea_value = Some(value)
+                    }
+                    _ => property.write_html(html),
+                },
+                Aspect::Style(style) => style.write_css(&mut css),
+                Aspect::Class(_) | Aspect::Listener(_) => {}
+            }
+        }
+        if !css.is_empty() {
+            render::attribute(html, "style", Some(&css));
+        }
+
+        match self.ns {
+            Ns::Html if render::is_void(self.name) => html.push('>'),
+            Ns::Svg if self.children.is_empty() => html.push_str("/>"),
+            _ => {
+                html.push('>');
+                match textarea_value {
+                    Some(value) => render::text(html, value),
+                    None if self.ns == Ns::Html && render::is_raw_text(self.name) => {
+                        let mut text = String::new();
+                        self.children.write_text(&mut text);
+                        render::raw_text(html, self.name, &text);
+                    }
+                    None => self.children.write_html(html),
+                }
+                html.push_str("</");
+                html.push_str(self.name);
+                html.push('>');
+            }
+        }
+    }
+
     pub fn with<W: With<C>>(mut self, with: W) -> Self {
         with.with(&mut self.children);
         self
@@ -230,9 +458,7 @@ impl<Message: 'static> VNonKeyedElement<Message> {
             .collect();
         let ref_ = {
             let f = f.clone();
-            ref_.map(|ref_| {
-                Box::new(move |el| f(ref_(el))) as Box<dyn Fn(Option<web::Element>) -> NewMessage>
-            })
+            ref_.map(|ref_| Box::new(move |el| f(ref_(el))) as Ref<NewMessage>)
         };
         let children = NonKeyed(
             children
@@ -254,18 +480,27 @@ impl<Message: 'static> VNonKeyedElement<Message> {
 }
 
 impl<Message: 'static> VKeyedElement<Message> {
-    pub fn push<N: Into<VNode<Message>>>(mut self, key: u64, vnode: N) -> Self {
-        self.children.0.push((key, vnode.into()));
+    pub fn push<K: Hash + Eq + Debug + 'static, N: Into<VNode<Message>>>(
+        mut self,
+        key: K,
+        vnode: N,
+    ) -> Self {
+        self.children.0.push((Key::new(key), vnode.into()));
         self
     }
 
-    pub fn append<N: Into<VNode<Message>>, I: IntoIterator<Item = (u64, N)>>(
+    pub fn append<
+        K: Hash + Eq + Debug + 'static,
+        N: Into<VNode<Message>>,
+        I: IntoIterator<Item = (K, N)>,
+    >(
         mut self,
         i: I,
     ) -> Self {
-        self.children
-            .0
-            .extend(i.into_iter().map(|(key, value)| (key, value.into())));
+        self.children.0.extend(
+            i.into_iter()
+                .map(|(key, value)| (Key::new(key), value.into())),
+        );
         self
     }
 
@@ -295,9 +530,7 @@ impl<Message: 'static> VKeyedElement<Message> {
             .collect();
         let ref_ = {
             let f = f.clone();
-            ref_.map(|ref_| {
-                Box::new(move |el| f(ref_(el))) as Box<dyn Fn(Option<web::Element>) -> NewMessage>
-            })
+            ref_.map(|ref_| Box::new(move |el| f(ref_(el))) as Ref<NewMessage>)
         };
         let children = Keyed(
             children
@@ -321,8 +554,21 @@ impl<Message: 'static> VKeyedElement<Message> {
 pub trait Children {
     type Message;
     fn new() -> Self;
-    fn create(&mut self, node: &web::Node, mailbox: &Mailbox<Self::Message>);
-    fn patch(&mut self, old: &mut Self, old_node: &web::Node, mailbox: &Mailbox<Self::Message>);
+    fn create(&mut self, node: &Node, mailbox: &Mailbox<Self::Message>);
+    fn hydrate(&mut self, node: &Node, mailbox: &Mailbox<Self::Message>);
+    fn patch(&mut self, old: &mut Self, old_node: &Node, mailbox: &Mailbox<Self::Message>);
+    fn did_remove(&self, mailbox: &Mailbox<Self::Message>);
+    // Returns whether any child is on `path`; see `VNode::delegate`.
+    fn delegate(
+        &self,
+        path: &[Node],
+        name: &str,
+        event: &Event,
+        messages: &mut Vec<Self::Message>,
+    ) -> bool;
+    fn is_empty(&self) -> bool;
+    fn write_html(&self, html: &mut String);
+    fn write_text(&self, text: &mut String);
 }
 
 impl<Message: 'static> Children for NonKeyed<Message> {
@@ -332,14 +578,23 @@ impl<Message: 'static> Children for NonKeyed<Message> {
         NonKeyed(Vec::new())
     }
 
-    fn create(&mut self, node: &web::Node, mailbox: &Mailbox<Message>) {
+    fn create(&mut self, node: &Node, mailbox: &Mailbox<Message>) {
         for child in &mut self.0 {
             let child_node = child.create(mailbox);
-            node.append_child(&child_node).unwrap_throw();
+            dom::with(|dom| dom.append_child(node, &child_node));
+        }
+    }
+
+    fn hydrate(&mut self, node: &Node, mailbox: &Mailbox<Message>) {
+        let mut next = dom::with(|dom| dom.first_child(node));
+        for child in &mut self.0 {
+            child.hydrate(next, node, mailbox);
+            next = child.next_sibling();
         }
+        hydrate::remove_rest(node, next);
     }
 
-    fn patch(&mut self, old: &mut Self, old_node: &web::Node, mailbox: &Mailbox<Message>) {
+    fn patch(&mut self, old: &mut Self, old_node: &Node, mailbox: &Mailbox<Message>) {
         for (old, new) in old.0.iter_mut().zip(&mut self.0) {
             new.patch(old, mailbox);
         }
@@ -350,7 +605,41 @@ impl<Message: 'static> Children for NonKeyed<Message> {
 
         for new in self.0.iter_mut().skip(old.0.len()) {
             let new_node = new.create(mailbox);
-            old_node.append_child(&new_node).unwrap_throw();
+            dom::with(|dom| dom.append_child(old_node, &new_node));
+        }
+    }
+
+    fn did_remove(&self, mailbox: &Mailbox<Message>) {
+        for child in &self.0 {
+            child.did_remove(mailbox);
+        }
+    }
+
+    fn delegate(
+        &self,
+        path: &[Node],
+        name: &str,
+        event: &Event,
+        messages: &mut Vec<Message>,
+    ) -> bool {
+        self.0
+            .iter()
+            .any(|child| child.delegate(path, name, event, messages))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn write_html(&self, html: &mut String) {
+        for child in &self.0 {
+            child.write_html(html);
+        }
+    }
+
+    fn write_text(&self, text: &mut String) {
+        for child in &self.0 {
+            child.write_text(text);
         }
     }
 }
@@ -362,19 +651,33 @@ impl<Message: 'static> Children for Keyed<Message> {
         Keyed(Vec::new())
     }
 
-    fn create(&mut self, node: &web::Node, mailbox: &Mailbox<Message>) {
+    fn create(&mut self, node: &Node, mailbox: &Mailbox<Message>) {
+        diagnostics::check_keys(node, self.0.iter().map(|(key, _)| key));
         for (_, child) in &mut self.0 {
             let child_node = child.create(mailbox);
-            node.append_child(&child_node).unwrap_throw();
+            dom::with(|dom| dom.append_child(node, &child_node));
         }
     }
 
-    fn patch(&mut self, old: &mut Self, parent_node: &web::Node, mailbox: &Mailbox<Message>) {
+    fn hydrate(&mut self, node: &Node, mailbox: &Mailbox<Message>) {
+        let mut next = dom::with(|dom| dom.first_child(node));
+        for (_, child) in &mut self.0 {
+            child.hydrate(next, node, mailbox);
+            next = child.next_sibling();
+        }
+        hydrate::remove_rest(node, next);
+    }
+
+    fn patch(&mut self, old: &mut Self, parent_node: &Node, mailbox: &Mailbox<Message>) {
+        diagnostics::check_keys(parent_node, self.0.iter().map(|(key, _)| key));
         let new = &mut self.0;
         let old = &mut old.0;
 
         if new.is_empty() {
-            parent_node.set_text_content(Some(""));
+            dom::with(|dom| dom.set_text_content(parent_node, ""));
+            for (_, old_vnode) in old.iter() {
+                old_vnode.did_remove(mailbox);
+            }
             return;
         }
 
@@ -413,32 +716,108 @@ impl<Message: 'static> Children for Keyed<Message> {
 
         let mut key_to_old_index = HashMap::default();
         for (index, (key, _)) in (start_index..).zip(old[start_index..end_index_old].iter_mut()) {
-            key_to_old_index.insert(*key, index);
+            key_to_old_index.insert(key.clone(), index);
         }
 
-        for (index, (key, new_vnode)) in
-            (start_index..).zip(new[start_index..end_index_new].iter_mut())
+        // The old index of each remaining new child, if it is reused. Children whose old indices
+        // form the longest increasing subsequence are already in order and stay where they are;
+        // only the rest are moved.
+        let sources = new[start_index..end_index_new]
+            .iter()
+            .map(|(key, _)| key_to_old_index.remove(key))
+            .collect::<Vec<_>>();
+        let stays = longest_increasing_subsequence(&sources);
+
+        // Walk backwards so that the node each child must precede is always already in place.
+        let mut next_sibling = new.get(end_index_new).and_then(|(_, vnode)| vnode.node());
+        for (index, (_, new_vnode)) in new[start_index..end_index_new].iter_mut().enumerate().rev()
         {
-            let reordered = if let Some(old_index) = key_to_old_index.remove(key) {
-                let (_, ref mut old_vnode) = old[old_index];
-                new_vnode.patch(old_vnode, mailbox);
-                old_index != index
-            } else {
-                new_vnode.create(mailbox);
-                true
+            match sources[index] {
+                Some(old_index) => new_vnode.patch(&mut old[old_index].1, mailbox),
+                None => new_vnode.create(mailbox),
             };
-            if reordered {
-                let next_sibling = old.get(index + 1).and_then(|(_, vnode)| vnode.node());
-                parent_node
-                    .insert_before(&new_vnode.node().unwrap_throw(), next_sibling.as_ref())
-                    .unwrap_throw();
+            if !stays[index] {
+                // A fragment has to move every one of its nodes.
+                let mut nodes = Vec::new();
+                new_vnode.nodes(&mut nodes);
+                dom::with(|dom| {
+                    for node in &nodes {
+                        dom.insert_before(parent_node, node, next_sibling.as_ref());
+                    }
+                });
             }
+            next_sibling = new_vnode.node();
         }
 
         for index in key_to_old_index.values() {
             old[*index].1.remove(mailbox);
         }
     }
+
+    fn did_remove(&self, mailbox: &Mailbox<Message>) {
+        for (_, child) in &self.0 {
+            child.did_remove(mailbox);
+        }
+    }
+
+    fn delegate(
+        &self,
+        path: &[Node],
+        name: &str,
+        event: &Event,
+        messages: &mut Vec<Message>,
+    ) -> bool {
+        self.0
+            .iter()
+            .any(|(_, child)| child.delegate(path, name, event, messages))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn write_html(&self, html: &mut String) {
+        for (_, child) in &self.0 {
+            child.write_html(html);
+        }
+    }
+
+    fn write_text(&self, text: &mut String) {
+        for (_, child) in &self.0 {
+            child.write_text(text);
+        }
+    }
+}
+
+// Marks the elements of one of the longest strictly increasing subsequences of the `Some`s in
+// `sequence`, in O(n log n).
+fn longest_increasing_subsequence(sequence: &[Option<usize>]) -> Vec<bool> {
+    // `tails[i]` is the index of the smallest last element of an increasing subsequence of
+    // length `i + 1` seen so far; `previous` links each element to the one before it.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; sequence.len()];
+    for (index, value) in sequence.iter().enumerate() {
+        let value = match value {
+            Some(value) => *value,
+            None => continue,
+        };
+        let length = tails.partition_point(|&tail| sequence[tail].unwrap_throw() < value);
+        if length > 0 {
+            previous[index] = Some(tails[length - 1]);
+        }
+        if length == tails.len() {
+            tails.push(index);
+        } else {
+            tails[length] = index;
+        }
+    }
+    let mut result = vec![false; sequence.len()];
+    let mut current = tails.last().copied();
+    while let Some(index) = current {
+        result[index] = true;
+        current = previous[index];
+    }
+    result
 }
 
 pub trait With<C: Children> {
@@ -473,8 +852,25 @@ impl<Message: 'static, T: Into<VNode<Message>>> With<NonKeyed<Message>> for T {
     }
 }
 
-impl<Message: 'static, T: Into<VNode<Message>>> With<Keyed<Message>> for (u64, T) {
-    fn with(self, children: &mut Keyed<Message>) {
-        children.0.push((self.0, self.1.into()));
-    }
+// Keys of other types can be passed to `push` and `append`, or wrapped in a `Key`.
+macro_rules! keys {
+    ($($ty:ty)*) => {
+        $(
+            impl<Message: 'static, T: Into<VNode<Message>>> With<Keyed<Message>> for ($ty, T) {
+                fn with(self, children: &mut Keyed<Message>) {
+                    children.0.push((Key::new(self.0), self.1.into()));
+                }
+            }
+        )*
+    };
+}
+
+keys! {
+    i8 i16 i32 i64 i128 isize
+    u8 u16 u32 u64 u128 usize
+    bool char &'static str String S Key
+}
+
+fn has_token(class: &str, token: &str) -> bool {
+    class.split_whitespace().any(|t| t == token)
 }
@@ -1,8 +1,13 @@
-use crate::{Lazy, Mailbox, VKeyedElement, VNonKeyedElement, VText};
+use crate::dom::{self, Event, Node};
+#[cfg(panic = "unwind")]
+use crate::Boundary;
+use crate::{
+    diagnostics, hydrate, Fragment, Lazy, Mailbox, Portal, VComponent, VKeyedElement,
+    VNonKeyedElement, VText,
+};
 use derivative::Derivative;
 use std::rc::Rc;
 use wasm_bindgen::UnwrapThrowExt;
-use web_sys as web;
 
 #[derive(Derivative)]
 #[derivative(Debug(bound = ""))]
@@ -11,69 +16,178 @@ pub enum VNode<Message: 'static> {
     KeyedElement(VKeyedElement<Message>),
     Text(VText),
     Lazy(Lazy<Message>),
+    Portal(Portal<Message>),
+    Fragment(Fragment<Message>),
+    Component(VComponent<Message>),
+    #[cfg(panic = "unwind")]
+    Boundary(Boundary<Message>),
 }
 
 impl<Message: 'static> VNode<Message> {
-    pub fn create(&mut self, mailbox: &Mailbox<Message>) -> web::Node {
-        let node = match self {
-            VNode::Element(element) => element.create(mailbox).into(),
-            VNode::KeyedElement(keyed_element) => keyed_element.create(mailbox).into(),
-            VNode::Text(text) => text.create().into(),
+    pub fn create(&mut self, mailbox: &Mailbox<Message>) -> Node {
+        let _pass = diagnostics::enter();
+        // Elements call their own `did_create`, so it is not called again here.
+        match self {
+            VNode::Element(element) => element.create(mailbox),
+            VNode::KeyedElement(keyed_element) => keyed_element.create(mailbox),
+            VNode::Text(text) => text.create(),
             VNode::Lazy(lazy) => lazy.create(mailbox),
-        };
-
-        self.did_create(&node, mailbox);
-
-        node
+            VNode::Portal(portal) => portal.create(mailbox),
+            VNode::Fragment(fragment) => fragment.create(mailbox),
+            VNode::Component(component) => component.create(mailbox),
+            #[cfg(panic = "unwind")]
+            VNode::Boundary(boundary) => boundary.create(mailbox),
+        }
     }
 
-    pub fn did_create(&self, node: &web::Node, mailbox: &Mailbox<Message>) {
+    pub fn did_create(&self, node: &Node, mailbox: &Mailbox<Message>) {
         match self {
             VNode::Element(element) => element.did_create(node, mailbox),
             VNode::KeyedElement(keyed_element) => keyed_element.did_create(node, mailbox),
             VNode::Text(_) => {}
             VNode::Lazy(lazy) => lazy.did_create(node, mailbox),
+            VNode::Portal(_) | VNode::Fragment(_) | VNode::Component(_) => {}
+            #[cfg(panic = "unwind")]
+            VNode::Boundary(_) => {}
         }
     }
 
-    pub fn patch(&mut self, old: &mut Self, mailbox: &Mailbox<Message>) -> web::Node {
+    pub fn hydrate(
+        &mut self,
+        node: Option<Node>,
+        parent: &Node,
+        mailbox: &Mailbox<Message>,
+    ) -> Node {
+        let _pass = diagnostics::enter();
+        let node = hydrate::skip_comments(node);
+        match self {
+            VNode::Element(element) => element.hydrate(node, parent, mailbox),
+            VNode::KeyedElement(keyed_element) => keyed_element.hydrate(node, parent, mailbox),
+            VNode::Text(text) => text.hydrate(node, parent),
+            VNode::Lazy(lazy) => lazy.hydrate(node, parent, mailbox),
+            VNode::Portal(portal) => portal.hydrate(node, parent, mailbox),
+            VNode::Fragment(fragment) => fragment.hydrate(node, parent, mailbox),
+            VNode::Component(component) => component.hydrate(node, parent, mailbox),
+            #[cfg(panic = "unwind")]
+            VNode::Boundary(boundary) => boundary.hydrate(node, parent, mailbox),
+        }
+    }
+
+    pub fn patch(&mut self, old: &mut Self, mailbox: &Mailbox<Message>) -> Node {
+        let _pass = diagnostics::enter();
         match (self, old) {
             (VNode::Element(ref mut e1), VNode::Element(ref mut e2)) if e1.name == e2.name => {
-                e1.patch(e2, mailbox).into()
+                e1.patch(e2, mailbox)
             }
             (VNode::KeyedElement(ref mut e1), VNode::KeyedElement(ref mut e2))
                 if e1.name == e2.name =>
             {
-                e1.patch(e2, mailbox).into()
+                e1.patch(e2, mailbox)
             }
-            (VNode::Text(ref mut t1), VNode::Text(ref mut t2)) => t1.patch(t2).into(),
+            (VNode::Text(ref mut t1), VNode::Text(ref mut t2)) => t1.patch(t2),
             (VNode::Lazy(ref mut l1), VNode::Lazy(ref mut l2)) => l1.patch(l2, mailbox),
+            (VNode::Portal(ref mut p1), VNode::Portal(ref mut p2)) => p1.patch(p2, mailbox),
+            (VNode::Fragment(ref mut f1), VNode::Fragment(ref mut f2)) => f1.patch(f2, mailbox),
+            (VNode::Component(ref mut c1), VNode::Component(ref mut c2)) if c1.is_same(c2) => {
+                c1.patch(c2, mailbox)
+            }
+            #[cfg(panic = "unwind")]
+            (VNode::Boundary(ref mut b1), VNode::Boundary(ref mut b2)) => b1.patch(b2, mailbox),
             (self_, old) => {
                 let old_node = old.node().unwrap_throw();
-                let parent_node = old_node.parent_node().unwrap_throw();
+                let parent_node = dom::with(|dom| dom.parent(&old_node)).unwrap_throw();
+                if let Some(name) = old.ref_name() {
+                    diagnostics::warn(&parent_node, || {
+                        format!(
+                            "<{}> with a ref_ was replaced, so its ref_ fired again",
+                            name
+                        )
+                    });
+                }
                 let node = self_.create(mailbox);
-                parent_node.replace_child(&node, &old_node).unwrap_throw();
-                old.did_remove(mailbox);
-                node
+                dom::with(|dom| dom.insert_before(&parent_node, &node, Some(&old_node)));
+                old.remove(mailbox);
+                self_.node().unwrap_throw()
             }
         }
     }
 
-    pub fn node(&self) -> Option<web::Node> {
+    // The name of the element if it has a `ref_`.
+    fn ref_name(&self) -> Option<&'static str> {
         match self {
-            VNode::Element(element) => element.node().map(Into::into),
-            VNode::KeyedElement(keyed_element) => keyed_element.node().map(Into::into),
-            VNode::Text(text) => text.node().map(Into::into),
+            VNode::Element(element) if element.has_ref() => Some(element.name),
+            VNode::KeyedElement(keyed_element) if keyed_element.has_ref() => {
+                Some(keyed_element.name)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn node(&self) -> Option<Node> {
+        match self {
+            VNode::Element(element) => element.node(),
+            VNode::KeyedElement(keyed_element) => keyed_element.node(),
+            VNode::Text(text) => text.node(),
             VNode::Lazy(lazy) => lazy.node(),
+            VNode::Portal(portal) => portal.node(),
+            VNode::Fragment(fragment) => fragment.node(),
+            VNode::Component(component) => component.node(),
+            #[cfg(panic = "unwind")]
+            VNode::Boundary(boundary) => boundary.node(),
         }
     }
 
-    pub fn remove(&self, mailbox: &Mailbox<Message>) {
-        if let Some(node) = self.node() {
-            if let Some(parent_node) = node.parent_node() {
-                parent_node.remove_child(&node).unwrap_throw();
+    // Every top level DOM node, which is more than one only for fragments.
+    pub(crate) fn nodes(&self, nodes: &mut Vec<Node>) {
+        match self {
+            VNode::Lazy(lazy) => lazy.nodes(nodes),
+            VNode::Fragment(fragment) => fragment.nodes(nodes),
+            VNode::Component(component) => component.nodes(nodes),
+            #[cfg(panic = "unwind")]
+            VNode::Boundary(boundary) => boundary.nodes(nodes),
+            _ => nodes.extend(self.node()),
+        }
+    }
+
+    // Collects the messages from the delegated listeners for `event` on the nodes in `path`,
+    // innermost first. `path` runs from this node's level down to the event's target; returns
+    // whether this node is on it.
+    pub(crate) fn delegate(
+        &self,
+        path: &[Node],
+        name: &str,
+        event: &Event,
+        messages: &mut Vec<Message>,
+    ) -> bool {
+        match self {
+            VNode::Element(element) => element.delegate(path, name, event, messages),
+            VNode::KeyedElement(keyed_element) => {
+                keyed_element.delegate(path, name, event, messages)
             }
+            VNode::Lazy(lazy) => lazy.delegate(path, name, event, messages),
+            VNode::Fragment(fragment) => fragment.delegate(path, name, event, messages),
+            #[cfg(panic = "unwind")]
+            VNode::Boundary(boundary) => boundary.delegate(path, name, event, messages),
+            VNode::Text(_) | VNode::Portal(_) | VNode::Component(_) => false,
         }
+    }
+
+    pub(crate) fn next_sibling(&self) -> Option<Node> {
+        let mut nodes = Vec::new();
+        self.nodes(&mut nodes);
+        dom::with(|dom| dom.next_sibling(nodes.last()?))
+    }
+
+    pub fn remove(&self, mailbox: &Mailbox<Message>) {
+        let mut nodes = Vec::new();
+        self.nodes(&mut nodes);
+        dom::with(|dom| {
+            for node in &nodes {
+                if let Some(parent_node) = dom.parent(node) {
+                    dom.remove_child(&parent_node, node);
+                }
+            }
+        });
         self.did_remove(mailbox);
     }
 
@@ -83,6 +197,39 @@ impl<Message: 'static> VNode<Message> {
             VNode::KeyedElement(keyed_element) => keyed_element.did_remove(mailbox),
             VNode::Text(_) => {}
             VNode::Lazy(lazy) => lazy.did_remove(mailbox),
+            VNode::Portal(portal) => portal.did_remove(mailbox),
+            VNode::Fragment(fragment) => fragment.did_remove(mailbox),
+            VNode::Component(component) => component.did_remove(),
+            #[cfg(panic = "unwind")]
+            VNode::Boundary(boundary) => boundary.did_remove(mailbox),
+        }
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html);
+        html
+    }
+
+    // Writes the text of a child of an element such as `<script>`, which can only contain text.
+    pub(crate) fn write_text(&self, text: &mut String) {
+        if let VNode::Text(vtext) = self {
+            text.push_str(vtext.value());
+        }
+    }
+
+    pub(crate) fn write_html(&self, html: &mut String) {
+        match self {
+            VNode::Element(element) => element.write_html(html),
+            VNode::KeyedElement(keyed_element) => keyed_element.write_html(html),
+            VNode::Text(text) => text.write_html(html),
+            VNode::Lazy(lazy) => lazy.write_html(html),
+            // Portals render into another container, which does not exist on the server.
+            VNode::Portal(_) => {}
+            VNode::Fragment(fragment) => fragment.write_html(html),
+            VNode::Component(component) => component.write_html(html),
+            #[cfg(panic = "unwind")]
+            VNode::Boundary(boundary) => boundary.write_html(html),
         }
     }
 
@@ -102,6 +249,11 @@ impl<Message: 'static> VNode<Message> {
             VNode::KeyedElement(keyed_element) => VNode::KeyedElement(keyed_element.do_map(f)),
             VNode::Text(text) => VNode::Text(text),
             VNode::Lazy(lazy) => VNode::Lazy(lazy.do_map(f)),
+            VNode::Portal(portal) => VNode::Portal(portal.do_map(f)),
+            VNode::Fragment(fragment) => VNode::Fragment(fragment.do_map(f)),
+            VNode::Component(component) => VNode::Component(component.do_map(f)),
+            #[cfg(panic = "unwind")]
+            VNode::Boundary(boundary) => VNode::Boundary(boundary.do_map(f)),
         }
     }
 }
@@ -130,6 +282,32 @@ impl<Message: 'static> From<Lazy<Message>> for VNode<Message> {
     }
 }
 
+impl<Message: 'static> From<Portal<Message>> for VNode<Message> {
+    fn from(portal: Portal<Message>) -> Self {
+        VNode::Portal(portal)
+    }
+}
+
+impl<Message: 'static> From<Fragment<Message>> for VNode<Message> {
+    fn from(fragment: Fragment<Message>) -> Self {
+        VNode::Fragment(fragment)
+    }
+}
+
+impl<Message: 'static> From<VComponent<Message>> for VNode<Message> {
+    fn from(component: VComponent<Message>) -> Self {
+        VNode::Component(component)
+    }
+}
+
+#[cfg(panic = "unwind")]
+impl<Message: 'static> From<Boundary<Message>> for VNode<Message> {
+    fn from(boundary: Boundary<Message>) -> Self {
+        #[cfg(panic = "unwind")]
+        VNode::Boundary(boundary)
+    }
+}
+
 impl<Message> From<&'static str> for VNode<Message> {
     fn from(str: &'static str) -> Self {
         VText::new(str).into()
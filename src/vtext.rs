@@ -1,11 +1,11 @@
-use crate::S;
+use crate::dom::{self, Node};
+use crate::{hydrate, render, S};
 use wasm_bindgen::UnwrapThrowExt;
-use web_sys as web;
 
 #[derive(Debug)]
 pub struct VText {
     value: S,
-    node: Option<web::Text>,
+    node: Option<Node>,
 }
 
 impl VText {
@@ -16,26 +16,63 @@ impl VText {
         }
     }
 
-    pub fn create(&mut self) -> web::Text {
-        let node = web::window()
-            .unwrap_throw()
-            .document()
-            .unwrap_throw()
-            .create_text_node(&self.value);
+    pub fn create(&mut self) -> Node {
+        let node = dom::with(|dom| dom.create_text(&self.value));
         self.node = Some(node.clone());
         node
     }
 
-    pub fn patch(&mut self, old: &mut VText) -> web::Text {
+    pub fn hydrate(&mut self, node: Option<Node>, parent: &Node) -> Node {
+        // Empty text renders nothing on the server, so there is never a node to adopt.
+        if self.value.is_empty() {
+            let text = self.create();
+            dom::with(|dom| dom.insert_before(parent, &text, node.as_ref()));
+            return text;
+        }
+        match node
+            .as_ref()
+            .and_then(|node| dom::with(|dom| dom.text(node)))
+        {
+            Some(text) => {
+                let node = node.unwrap_throw();
+                if text != self.value {
+                    hydrate::mismatch(&format!("{:?}", self.value), Some(&node));
+                    dom::with(|dom| dom.set_text(&node, &self.value));
+                }
+                self.node = Some(node.clone());
+                node
+            }
+            None => {
+                hydrate::mismatch(&format!("{:?}", self.value), node.as_ref());
+                let text = self.create();
+                hydrate::replace(parent, &text, node.as_ref());
+                text
+            }
+        }
+    }
+
+    pub fn patch(&mut self, old: &mut VText) -> Node {
         let node = old.node.clone().unwrap_throw();
         if self.value != old.value {
-            node.set_data(&self.value);
+            dom::with(|dom| dom.set_text(&node, &self.value));
         }
         self.node = Some(node.clone());
         node
     }
 
-    pub fn node(&self) -> Option<web::Text> {
+    pub fn node(&self) -> Option<Node> {
         self.node.clone()
     }
+
+    pub(crate) fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub(crate) fn write_html(&self, html: &mut String) {
+        // Keep adjacent text nodes apart so that they can be hydrated separately.
+        if !html.is_empty() && !html.ends_with('>') {
+            html.push_str("<!---->");
+        }
+        render::text(html, &self.value);
+    }
 }
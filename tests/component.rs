@@ -0,0 +1,184 @@
+use draco::component::Scope;
+use draco::html as h;
+use draco::testing::Harness;
+use draco::{Cmd, Component, Mailbox, Scheduler, VComponent, VNode};
+use std::cell::Cell;
+
+thread_local! {
+    static DESTROYED: Cell<usize> = const { Cell::new(0) };
+}
+
+struct Counter {
+    value: i32,
+}
+
+enum CounterMessage {
+    Increment,
+}
+
+impl Component for Counter {
+    // The step, and the value at which the counter emits `()` and starts over.
+    type Props = (i32, i32);
+    type Message = CounterMessage;
+    type Event = ();
+
+    fn create(_: &(i32, i32)) -> Self {
+        Counter { value: 0 }
+    }
+
+    fn update(
+        &mut self,
+        &(step, limit): &(i32, i32),
+        message: CounterMessage,
+        scope: &Scope<Self>,
+    ) -> Cmd<CounterMessage> {
+        match message {
+            CounterMessage::Increment => self.value += step,
+        }
+        if self.value >= limit {
+            self.value = 0;
+            scope.emit(());
+        }
+        Cmd::none()
+    }
+
+    fn changed(&mut self, _: &(i32, i32), _: &(i32, i32)) -> Cmd<CounterMessage> {
+        self.value = 0;
+        Cmd::none()
+    }
+
+    fn view(&self, &(step, _): &(i32, i32)) -> VNode<CounterMessage> {
+        h::button()
+            .on("click", |_| CounterMessage::Increment)
+            .with((self.value, "+", step))
+            .into()
+    }
+
+    fn destroyed(&mut self) {
+        DESTROYED.with(|destroyed| destroyed.set(destroyed.get() + 1));
+    }
+}
+
+#[derive(Default)]
+struct Parent {
+    step: i32,
+    laps: usize,
+    is_shown: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Message {
+    Step(i32),
+    Lap,
+    Toggle,
+}
+
+impl draco::Application for Parent {
+    type Message = Message;
+
+    fn update(&mut self, message: Message, _: &Mailbox<Message>) -> Cmd<Message> {
+        match message {
+            Message::Step(step) => self.step = step,
+            Message::Lap => self.laps += 1,
+            Message::Toggle => self.is_shown = !self.is_shown,
+        }
+        Cmd::none()
+    }
+
+    fn view(&self) -> VNode<Message> {
+        h::div()
+            .with((
+                self.laps,
+                if self.is_shown {
+                    VComponent::with_events::<Counter>((self.step, 3), |()| Message::Lap).into()
+                } else {
+                    VNode::from("")
+                },
+            ))
+            .into()
+    }
+}
+
+#[test]
+fn t_component() {
+    let harness = Harness::new(Parent {
+        step: 1,
+        ..Parent::default()
+    });
+    harness.send(Message::Toggle);
+    assert_eq!(harness.html(), "<div>0<button>0+1</button></div>");
+
+    let button = harness.by_tag("button").remove(0);
+    harness.click(&button);
+    harness.click(&button);
+    assert_eq!(harness.html(), "<div>0<button>2+1</button></div>");
+    // Only the component rendered; the parent has not received any message.
+    assert_eq!(harness.messages(), [Message::Toggle]);
+
+    harness.click(&button);
+    assert_eq!(harness.html(), "<div>1<button>0+1</button></div>");
+    assert_eq!(harness.messages(), [Message::Toggle, Message::Lap]);
+
+    harness.click(&button);
+    harness.send(Message::Step(2));
+    assert_eq!(harness.html(), "<div>1<button>0+2</button></div>");
+    assert_eq!(harness.by_tag("button")[0], button);
+    harness.click(&button);
+    harness.click(&button);
+    assert_eq!(harness.app().laps, 2);
+
+    harness.send(Message::Toggle);
+    assert_eq!(harness.html(), "<div>2</div>");
+    assert_eq!(DESTROYED.with(Cell::get), 1);
+    harness.click(&button);
+    assert_eq!(harness.app().laps, 2);
+}
+
+// Switches between a `<p>` and a `<span>` when clicked.
+struct Switch(bool);
+
+impl Component for Switch {
+    type Props = ();
+    type Message = ();
+    type Event = ();
+
+    fn create(_: &()) -> Self {
+        Switch(false)
+    }
+
+    fn update(&mut self, _: &(), _: (), _: &Scope<Self>) -> Cmd<()> {
+        self.0 = !self.0;
+        Cmd::none()
+    }
+
+    fn view(&self, _: &()) -> VNode<()> {
+        if self.0 {
+            h::span().on("click", |_| ()).into()
+        } else {
+            h::p().on("click", |_| ()).into()
+        }
+    }
+}
+
+struct Root;
+
+impl draco::Application for Root {
+    type Message = ();
+
+    fn view(&self) -> VNode<()> {
+        VComponent::new::<Switch>(()).into()
+    }
+}
+
+#[test]
+fn t_component_scheduled() {
+    let harness = Harness::with_scheduler(Root, Scheduler::AnimationFrame);
+    assert_eq!(harness.html(), "<p></p>");
+
+    harness.click(&harness.root());
+    harness.tick();
+    assert_eq!(harness.html(), "<p></p>");
+    harness.frame();
+    assert_eq!(harness.html(), "<span></span>");
+    assert_eq!(harness.root().name().as_deref(), Some("span"));
+}
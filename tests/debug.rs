@@ -0,0 +1,154 @@
+use draco::html as h;
+use draco::testing::Harness;
+use draco::{Cmd, Mailbox, VNode};
+
+#[derive(Clone, Default)]
+struct Counter(i32, String);
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Message {
+    Add(i32),
+    Say(String),
+}
+
+impl draco::Application for Counter {
+    type Message = Message;
+
+    fn update(&mut self, message: Message, _: &Mailbox<Message>) -> Cmd<Message> {
+        match message {
+            Message::Add(n) => self.0 += n,
+            Message::Say(text) => {
+                self.1 = text;
+                return Cmd::skip_render();
+            }
+        }
+        Cmd::none()
+    }
+
+    fn view(&self) -> VNode<Message> {
+        h::button()
+            .on("click", |_| Message::Add(1))
+            .with(self.0)
+            .into()
+    }
+}
+
+#[test]
+fn t_debugger() {
+    let harness = Harness::new(Counter(10, String::new()));
+    let debugger = harness.debugger();
+    harness.click(&harness.root());
+    harness.send(Message::Add(5));
+    harness.send(Message::Say("\"hi\"\n".into()));
+    assert_eq!(debugger.len(), 3);
+    assert_eq!(harness.html(), "<button>16</button>");
+
+    debugger.step_back();
+    debugger.step_back();
+    assert_eq!(debugger.index(), 1);
+    assert_eq!(harness.html(), "<button>11</button>");
+    debugger.go_to(0);
+    assert_eq!(harness.html(), "<button>10</button>");
+    debugger.step_forward();
+    assert_eq!(harness.app().0, 11);
+
+    debugger.replay(Counter(0, String::new()));
+    assert_eq!(debugger.index(), 3);
+    assert_eq!(harness.html(), "<button>6</button>");
+
+    #[cfg(feature = "serde")]
+    {
+        let export = debugger.export();
+        assert!(export.starts_with("[{\"time\":"));
+        assert!(export.contains(",\"message\":{\"Say\":\"\\\"hi\\\"\\n\"}}]"));
+
+        let other = Harness::new(Counter(10, String::new()));
+        let debugger = other.debugger();
+        debugger.import(&export).unwrap();
+        assert_eq!(debugger.index(), 3);
+        assert_eq!(other.html(), "<button>16</button>");
+        let messages: Vec<_> = debugger.entries().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages[..2], [Message::Add(1), Message::Add(5)]);
+        assert!(debugger.import("[{}]").is_err());
+    }
+
+    // Sending a message while an earlier snapshot is shown discards the later ones.
+    debugger.go_to(1);
+    harness.click(&harness.root());
+    assert_eq!(debugger.len(), 2);
+    assert_eq!(harness.html(), "<button>2</button>");
+}
+
+#[test]
+fn t_debugger_replay() {
+    use draco::task::Model;
+    use draco::{Middleware, MiddlewareChain, Task};
+
+    // Doubles every `Add`, and drops it while the count is above 100.
+    struct Double;
+
+    impl Middleware<Async> for Double {
+        fn before(&mut self, app: &Async, message: Message) -> Option<Message> {
+            match message {
+                Message::Add(_) if app.0 > 100 => None,
+                Message::Add(n) => Some(Message::Add(n * 2)),
+                message => Some(message),
+            }
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Async(i32);
+
+    impl draco::Application for Async {
+        type Message = Message;
+
+        fn update(&mut self, message: Message, _: &Mailbox<Message>) -> Cmd<Message> {
+            if let Message::Add(n) = message {
+                self.0 += n;
+            }
+            Cmd::none()
+        }
+
+        // `Say` sets the count to the length of the text, in a task.
+        fn update_async(message: Message, model: Model<Self>) -> Result<Task, Message> {
+            let text = match message {
+                Message::Say(text) => text,
+                message => return Err(message),
+            };
+            Ok(Task::new(async move {
+                if let Some(mut app) = model.lock().await {
+                    app.0 = text.len() as i32;
+                }
+            }))
+        }
+
+        fn middleware(&self) -> MiddlewareChain<Self> {
+            MiddlewareChain::new().with(Double)
+        }
+
+        fn view(&self) -> VNode<Message> {
+            h::p().with(self.0).into()
+        }
+    }
+
+    let harness = Harness::new(Async::default());
+    let debugger = harness.debugger();
+    harness.send(Message::Add(1));
+    harness.send(Message::Say("abc".into()));
+    harness.poll();
+    harness.send(Message::Add(100));
+    harness.send(Message::Add(1));
+    assert_eq!(harness.html(), "<p>203</p>");
+    // Messages are recorded before the middleware doubles or drops them.
+    let messages: Vec<_> = debugger.entries().into_iter().map(|e| e.message).collect();
+    assert_eq!(messages[2..], [Message::Add(100), Message::Add(1)]);
+
+    debugger.go_to(2);
+    assert_eq!(harness.html(), "<p>3</p>");
+    debugger.go_to(1);
+    assert_eq!(harness.html(), "<p>2</p>");
+    debugger.go_to(4);
+    assert_eq!(harness.html(), "<p>203</p>");
+}
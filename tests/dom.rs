@@ -0,0 +1,615 @@
+use draco::dom::{self, memory, Memory};
+use draco::{html as h, Mailbox, VNode};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+fn create<Message>(vnode: &mut VNode<Message>, mailbox: &Mailbox<Message>) -> memory::Node {
+    dom::set_backend(Memory);
+    vnode.create(mailbox).as_memory().unwrap().clone()
+}
+
+// Counts the calls to `insert_before`, passing every call on to `Memory`.
+struct Counting(Rc<Cell<usize>>);
+
+macro_rules! counting {
+    ($(fn $name:ident(&self $(, $arg:ident: $ty:ty)*) $(-> $ret:ty)?;)*) => {
+        impl dom::Backend for Counting {
+            fn insert_before(
+                &self,
+                parent: &dom::Node,
+                child: &dom::Node,
+                reference: Option<&dom::Node>,
+            ) {
+                self.0.set(self.0.get() + 1);
+                Memory.insert_before(parent, child, reference)
+            }
+
+            $(
+                fn $name(&self $(, $arg: $ty)*) $(-> $ret)? {
+                    Memory.$name($($arg),*)
+                }
+            )*
+        }
+    };
+}
+
+counting! {
+    fn create_element(&self, ns: dom::Ns, name: &str) -> dom::Node;
+    fn create_text(&self, value: &str) -> dom::Node;
+    fn create_fragment(&self) -> dom::Node;
+    fn node_name(&self, node: &dom::Node) -> String;
+    fn namespace_uri(&self, node: &dom::Node) -> Option<String>;
+    fn text(&self, node: &dom::Node) -> Option<String>;
+    fn set_text(&self, node: &dom::Node, value: &str);
+    fn query_selector(&self, selector: &str) -> Option<dom::Node>;
+    fn parent(&self, node: &dom::Node) -> Option<dom::Node>;
+    fn first_child(&self, node: &dom::Node) -> Option<dom::Node>;
+    fn next_sibling(&self, node: &dom::Node) -> Option<dom::Node>;
+    fn append_child(&self, parent: &dom::Node, child: &dom::Node);
+    fn replace_child(&self, parent: &dom::Node, child: &dom::Node, old_child: &dom::Node);
+    fn remove_child(&self, parent: &dom::Node, child: &dom::Node);
+    fn set_text_content(&self, node: &dom::Node, value: &str);
+    fn attribute_names(&self, element: &dom::Node) -> Vec<String>;
+    fn set_attribute(&self, element: &dom::Node, name: &str, value: &str);
+    fn remove_attribute(&self, element: &dom::Node, name: &str);
+    fn get_property(&self, element: &dom::Node, name: &str) -> Option<dom::Value>;
+    fn set_property(&self, element: &dom::Node, name: &str, value: &dom::Value);
+    fn remove_property(&self, element: &dom::Node, name: &str);
+    fn set_style(&self, element: &dom::Node, name: &str, value: &str, important: bool);
+    fn remove_style(&self, element: &dom::Node, name: &str);
+    fn class_name(&self, element: &dom::Node) -> String;
+    fn add_class(&self, element: &dom::Node, name: &str);
+    fn remove_class(&self, element: &dom::Node, name: &str);
+    fn set_class_name(&self, element: &dom::Node, value: &str);
+    fn add_listener(
+        &self,
+        element: &dom::Node,
+        name: &str,
+        handler: Rc<dyn Fn(dom::Event)>
+    ) -> Box<dyn std::any::Any>;
+    fn remove_listener(&self, element: &dom::Node, name: &str, listener: &dyn std::any::Any);
+}
+
+fn list(keys: &[u64]) -> VNode<()> {
+    h::keyed::ul()
+        .append(keys.iter().map(|&key| (key, h::li().with(key))))
+        .into()
+}
+
+#[test]
+fn t_keyed() {
+    let mailbox = Mailbox::new(|_| ());
+    let mut old = list(&[1, 2, 3, 4, 5]);
+    let ul = create(&mut old, &mailbox);
+    let lis = ul.children();
+
+    for keys in &[
+        &[5, 1, 2, 3, 4][..],
+        &[4, 3, 2, 1, 5],
+        &[1, 6, 2, 7, 5],
+        &[5, 2],
+        &[8, 2, 5, 9],
+        &[],
+        &[1, 2],
+    ] {
+        let mut new = list(keys);
+        new.patch(&mut old, &mailbox);
+        old = new;
+        let expected = keys.iter().map(|key| format!("<li>{}</li>", key));
+        assert_eq!(
+            ul.to_html(),
+            format!("<ul>{}</ul>", expected.collect::<String>())
+        );
+        if *keys == [5, 1, 2, 3, 4] {
+            assert_eq!(ul.children()[0], lis[4]);
+            assert_eq!(ul.children()[1..], lis[..4]);
+        }
+    }
+}
+
+#[test]
+fn t_keyed_shuffle() {
+    use std::collections::HashMap;
+    use xorshift::{Rng, SeedableRng, Xorshift128};
+
+    let mailbox = Mailbox::new(|_| ());
+    let mut rng: Xorshift128 = SeedableRng::from_seed(&[1, 2][..]);
+    let mut keys = (0..100).collect::<Vec<u64>>();
+    let mut old = list(&keys);
+    let ul = create(&mut old, &mailbox);
+    let mut next_key = 100;
+    for _ in 0..50 {
+        let nodes = keys
+            .iter()
+            .cloned()
+            .zip(ul.children())
+            .collect::<HashMap<_, _>>();
+        rng.shuffle(&mut keys);
+        keys.truncate(keys.len() - rng.gen_range(0, 10));
+        for _ in 0..rng.gen_range(0, 10) {
+            let index = rng.gen_range(0, keys.len() + 1);
+            keys.insert(index, next_key);
+            next_key += 1;
+        }
+        let mut new = list(&keys);
+        new.patch(&mut old, &mailbox);
+        old = new;
+        let expected = keys.iter().map(|key| format!("<li>{}</li>", key));
+        assert_eq!(
+            ul.to_html(),
+            format!("<ul>{}</ul>", expected.collect::<String>())
+        );
+        for (key, node) in keys.iter().zip(ul.children()) {
+            if let Some(old_node) = nodes.get(key) {
+                assert_eq!(*old_node, node);
+            }
+        }
+    }
+
+    let inserts = Rc::new(Cell::new(0));
+    dom::set_backend(Counting(inserts.clone()));
+    let last = keys.pop().unwrap();
+    keys.insert(0, last);
+    let mut new = list(&keys);
+    new.patch(&mut old, &mailbox);
+    assert_eq!(ul.children()[0].to_html(), format!("<li>{}</li>", last));
+    assert_eq!(inserts.get(), 1);
+}
+
+#[test]
+fn t_keyed_generic() {
+    use draco::Key;
+
+    let mailbox = Mailbox::new(|_| ());
+    let view = |ids: &[(&'static str, u32)]| -> VNode<()> {
+        h::keyed::ul()
+            .append(ids.iter().map(|&id| (id, h::li().with(id.0))))
+            .with(("footer".to_string(), h::li()))
+            .push(Key::new(ids.len()), h::li())
+            .into()
+    };
+    let mut old = view(&[("a", 1), ("b", 1), ("a", 2)]);
+    let ul = create(&mut old, &mailbox);
+    let lis = ul.children();
+    let mut new = view(&[("a", 2), ("a", 1), ("b", 1)]);
+    new.patch(&mut old, &mailbox);
+    let order = [2, 0, 1, 3, 4];
+    assert_eq!(ul.children(), order.map(|index| lis[index].clone()));
+
+    assert_eq!(Key::new("a"), Key::new(Key::new("a")));
+    assert_eq!(Key::new("a"), Key::new("a".to_string()));
+    assert_eq!(Key::new(1u32), Key::new(1u64));
+    assert_ne!(Key::new(1), Key::new("1"));
+    assert_eq!(Key::new(u128::MAX), Key::new(u128::MAX));
+    assert_eq!(format!("{:?}", Key::new("a")), "\"a\"");
+    assert_eq!(format!("{:?}", Key::new(("a", 1))), "(\"a\", 1)");
+}
+
+#[test]
+fn t_style() {
+    use draco::dom::Backend;
+
+    let mailbox = Mailbox::new(|_| ());
+    let view = |color: &'static str, gap: bool| -> VNode<()> {
+        let div = h::div().style("color", color).style("--gap", "4px");
+        match gap {
+            true => div.style("margin", "0 !important").into(),
+            false => div.into(),
+        }
+    };
+    let mut old = view("red", true);
+    assert_eq!(
+        old.to_html(),
+        r#"<div style="color: red; --gap: 4px; margin: 0 !important;"></div>"#
+    );
+    let div = create(&mut old, &mailbox);
+    assert_eq!(div.style("margin").as_deref(), Some("0 !important"));
+    // Set outside the view, e.g. by an animation.
+    Memory.set_style(&div.clone().into(), "opacity", "0.5", false);
+
+    let mut new = view("blue", false);
+    new.patch(&mut old, &mailbox);
+    assert_eq!(div.style("color").as_deref(), Some("blue"));
+    assert_eq!(div.style("--gap").as_deref(), Some("4px"));
+    assert_eq!(div.style("margin"), None);
+    assert_eq!(div.style("opacity").as_deref(), Some("0.5"));
+
+    let mut vnode: VNode<()> = h::div()
+        .attribute("style", "display: none")
+        .style("color", "red")
+        .into();
+    let html = r#"<div style="display: none; color: red;"></div>"#;
+    assert_eq!(vnode.to_html(), html);
+    assert_eq!(create(&mut vnode, &mailbox).to_html(), html);
+}
+
+#[test]
+fn t_classes() {
+    use draco::dom::Backend;
+
+    let mailbox = Mailbox::new(|_| ());
+    let view = |class: &'static str, active: bool| -> VNode<()> {
+        h::div()
+            .class(class)
+            .classes(vec!["item", "large"])
+            .class_if("active", active)
+            .into()
+    };
+    let mut old = view("card", true);
+    assert_eq!(
+        old.to_html(),
+        r#"<div class="card item large active"></div>"#
+    );
+    let div = create(&mut old, &mailbox);
+    // Added outside the view, e.g. by a third-party script.
+    Memory.add_class(&div.clone().into(), "focused");
+
+    let mut new = view("card", false);
+    new.patch(&mut old, &mailbox);
+    assert_eq!(div.class_name(), "card item large focused");
+    old = new;
+
+    let mut new = view("panel", true);
+    new.patch(&mut old, &mailbox);
+    assert_eq!(div.class_name(), "panel item large active");
+    old = new;
+
+    // A token in `class` stays when `class_if` no longer adds it.
+    let mut new = view("panel active", true);
+    new.patch(&mut old, &mailbox);
+    old = new;
+    let mut new = view("panel active", false);
+    new.patch(&mut old, &mailbox);
+    assert_eq!(div.class_name(), "panel active item large");
+
+    // `class` overrides a `class` attribute.
+    let mut vnode: VNode<()> = h::div().class("card").attribute("class", "panel").into();
+    let div = create(&mut vnode, &mailbox);
+    assert_eq!(div.to_html(), r#"<div class="card"></div>"#);
+
+    // The `class` attribute is the class name.
+    Memory.remove_attribute(&div.clone().into(), "class");
+    assert_eq!(div.class_name(), "");
+    assert_eq!(div.to_html(), "<div></div>");
+}
+
+#[test]
+fn t_listeners() {
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let mailbox = {
+        let messages = messages.clone();
+        Mailbox::new(move |message| messages.borrow_mut().push(message))
+    };
+    let click = |n: i32| h::div().on("click", move |_| n);
+    let mut old: VNode<i32> = click(1).on("keydown", |_| 0).into();
+    let div = create(&mut old, &mailbox);
+
+    // The closures registered on the first render are kept, so their order does not change.
+    let mut new: VNode<i32> = h::div().on("keydown", |_| 0).on("click", |_| 2).into();
+    new.patch(&mut old, &mailbox);
+    old = new;
+    assert_eq!(div.listeners(), ["click", "keydown"]);
+    div.dispatch(&memory::Event::new("click"));
+
+    let mut new: VNode<i32> = click(3).into();
+    new.patch(&mut old, &mailbox);
+    assert_eq!(div.listeners(), ["click"]);
+    div.dispatch(&memory::Event::new("click"));
+    div.dispatch(&memory::Event::new("keydown"));
+    assert_eq!(*messages.borrow(), [2, 3]);
+}
+
+#[test]
+fn t_aspects() {
+    let mailbox = Mailbox::new(|_| ());
+    let view = |ns: &[usize]| -> VNode<()> {
+        ns.iter()
+            .fold(h::div(), |div, n| {
+                div.attribute(format!("a{}", n), n.to_string())
+            })
+            .into()
+    };
+    // Few and many enough for both ways of finding the old aspects.
+    for &count in &[4, 40] {
+        let mut old = view(&(0..count).collect::<Vec<_>>());
+        let div = create(&mut old, &mailbox);
+        let mut new = view(&(0..count).filter(|n| n % 2 == 1).rev().collect::<Vec<_>>());
+        new.patch(&mut old, &mailbox);
+        for n in 0..count {
+            let expected = Some(n.to_string()).filter(|_| n % 2 == 1);
+            assert_eq!(div.attribute(&format!("a{}", n)), expected);
+        }
+    }
+}
+
+#[test]
+fn t_non_keyed() {
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let mailbox = {
+        let messages = messages.clone();
+        Mailbox::new(move |message| messages.borrow_mut().push(message))
+    };
+    let view = |n: i32| -> VNode<i32> {
+        h::div()
+            .class(format!("n{}", n))
+            .with((
+                h::input()
+                    .value(n.to_string())
+                    .attribute("data-n", n.to_string())
+                    .on("click", move |_| n),
+                if n % 2 == 0 {
+                    h::span().with(n).into()
+                } else {
+                    VNode::from(n)
+                },
+            ))
+            .into()
+    };
+
+    let mut old = view(0);
+    let div = create(&mut old, &mailbox);
+    assert_eq!(
+        div.to_html(),
+        "<div class=\"n0\"><input data-n=\"0\" value=\"0\"><span>0</span></div>"
+    );
+
+    let input = div.children()[0].clone();
+    input.dispatch(&memory::Event::new("click"));
+
+    let mut new = view(1);
+    new.patch(&mut old, &mailbox);
+    assert_eq!(
+        div.to_html(),
+        "<div class=\"n1\"><input data-n=\"1\" value=\"1\">1</div>"
+    );
+    assert_eq!(div.children()[0], input);
+    assert_eq!(input.listeners(), ["click"]);
+
+    input.dispatch(&memory::Event::new("click"));
+    assert_eq!(*messages.borrow(), [0, 1]);
+}
+
+#[test]
+fn t_portal() {
+    let mailbox = Mailbox::new(|_| ());
+    let target = memory::Node::element(dom::Ns::Html, "body");
+    let view = |items: &[&'static str], show: bool| -> VNode<()> {
+        h::div()
+            .with((
+                "a",
+                if show {
+                    draco::portal(dom::Node::from(target.clone()))
+                        .append(items.iter().map(|&item| h::p().with(item)))
+                        .into()
+                } else {
+                    VNode::from("")
+                },
+                "b",
+            ))
+            .into()
+    };
+
+    let mut old = view(&["x"], true);
+    let div = create(&mut old, &mailbox);
+    assert_eq!(div.to_html(), "<div>ab</div>");
+    assert_eq!(target.to_html(), "<body><div><p>x</p></div></body>");
+    let p = target.children()[0].children()[0].clone();
+
+    let mut new = view(&["y", "z"], true);
+    new.patch(&mut old, &mailbox);
+    old = new;
+    assert_eq!(target.to_html(), "<body><div><p>y</p><p>z</p></div></body>");
+    assert_eq!(target.children()[0].children()[0], p);
+
+    let mut new = view(&[], false);
+    new.patch(&mut old, &mailbox);
+    assert_eq!(div.to_html(), "<div>ab</div>");
+    assert_eq!(target.to_html(), "<body></body>");
+}
+
+#[test]
+fn t_fragment() {
+    let mailbox = Mailbox::new(|_| ());
+    let rows = |keys: &[u64]| -> VNode<()> {
+        h::keyed::table()
+            .append(keys.iter().map(|&key| {
+                (
+                    key,
+                    draco::fragment().with((
+                        h::tr().with(key),
+                        draco::fragment().append((0..key).map(|_| h::tr())),
+                    )),
+                )
+            }))
+            .into()
+    };
+    let html = |keys: &[u64]| {
+        let rows = keys
+            .iter()
+            .map(|&key| format!("<tr>{}</tr>{}", key, "<tr></tr>".repeat(key as usize)));
+        format!("<table>{}</table>", rows.collect::<String>())
+    };
+
+    let mut old = rows(&[1, 2, 3]);
+    let table = create(&mut old, &mailbox);
+    assert_eq!(table.to_html(), html(&[1, 2, 3]));
+
+    for keys in &[&[3, 1, 2][..], &[2, 4, 3], &[0, 2], &[3, 0, 1, 2], &[2]] {
+        let mut new = rows(keys);
+        new.patch(&mut old, &mailbox);
+        old = new;
+        assert_eq!(table.to_html(), html(keys));
+    }
+
+    let view = |n: usize| -> VNode<()> {
+        h::div()
+            .with((
+                "a",
+                if n == 0 {
+                    VNode::from(h::p())
+                } else {
+                    draco::fragment()
+                        .append((0..n).map(|n| h::span().with(n)))
+                        .into()
+                },
+                "b",
+            ))
+            .into()
+    };
+    let mut old = view(2);
+    let div = create(&mut old, &mailbox);
+    for &n in &[3, 1, 0, 2, 0] {
+        let mut new = view(n);
+        new.patch(&mut old, &mailbox);
+        old = new;
+        let spans = (0..n).map(|n| format!("<span>{}</span>", n));
+        let middle = if n == 0 {
+            "<p></p>".into()
+        } else {
+            spans.collect::<String>()
+        };
+        assert_eq!(div.to_html(), format!("<div>a{}b</div>", middle));
+    }
+    assert_eq!(
+        view(2).to_html(),
+        "<div>a<span>0</span><span>1</span>b</div>"
+    );
+}
+
+#[test]
+fn t_ref() {
+    let refs = Rc::new(RefCell::new(Vec::new()));
+    let mailbox = {
+        let refs = refs.clone();
+        Mailbox::new(move |node: Option<dom::Node>| refs.borrow_mut().push(node))
+    };
+    let mut vnode: VNode<_> = h::div().with(h::p().ref_(|node| node)).into();
+    let div = create(&mut vnode, &mailbox);
+    let p = div.children()[0].clone();
+    assert_eq!(*refs.borrow(), [Some(p.into())]);
+    vnode.remove(&mailbox);
+    assert_eq!(refs.borrow().len(), 2);
+    assert_eq!(refs.borrow()[1], None);
+}
+
+#[test]
+#[cfg(panic = "unwind")]
+fn t_boundary() {
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let mailbox = {
+        let messages = messages.clone();
+        Mailbox::new(move |message| messages.borrow_mut().push(message))
+    };
+    let view = |n: i32| -> VNode<String> {
+        h::div()
+            .with((
+                h::p().with(n),
+                draco::Boundary::new(
+                    move || {
+                        if n == 1 {
+                            panic!("n = {}", n);
+                        }
+                        h::span().with(n).into()
+                    },
+                    |error| h::b().with(error.to_string()).into(),
+                )
+                .on_error(|error| error),
+            ))
+            .into()
+    };
+
+    let mut old = view(0);
+    let div = create(&mut old, &mailbox);
+    assert_eq!(div.to_html(), "<div><p>0</p><span>0</span></div>");
+
+    for (n, html) in &[
+        (1, "<div><p>1</p><b>n = 1</b></div>"),
+        (1, "<div><p>1</p><b>n = 1</b></div>"),
+        (2, "<div><p>2</p><span>2</span></div>"),
+    ] {
+        let mut new = view(*n);
+        new.patch(&mut old, &mailbox);
+        old = new;
+        assert_eq!(div.to_html(), *html);
+    }
+    assert_eq!(*messages.borrow(), ["n = 1"]);
+    assert_eq!(view(1).to_html(), "<div><p>1</p><b>n = 1</b></div>");
+
+    // With inputs, failed children are only tried again once the inputs change.
+    let calls = Rc::new(Cell::new(0));
+    let view = |n: i32| -> VNode<String> {
+        let calls = calls.clone();
+        h::div()
+            .with(
+                draco::Boundary::new(
+                    move || {
+                        calls.set(calls.get() + 1);
+                        if n < 2 {
+                            panic!("n = {}", n);
+                        }
+                        h::span().with(n).into()
+                    },
+                    |error| h::b().with(error.to_string()).into(),
+                )
+                .inputs(n / 2),
+            )
+            .into()
+    };
+    let mut old = view(0);
+    let div = create(&mut old, &mailbox);
+    assert_eq!(div.to_html(), "<div><b>n = 0</b></div>");
+    for (n, html) in &[
+        (1, "<div><b>n = 0</b></div>"),
+        (2, "<div><span>2</span></div>"),
+    ] {
+        let mut new = view(*n);
+        new.patch(&mut old, &mailbox);
+        old = new;
+        assert_eq!(div.to_html(), *html);
+    }
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn t_diagnostics() {
+    let warnings = Rc::new(RefCell::new(Vec::<String>::new()));
+    {
+        let warnings = warnings.clone();
+        draco::diagnostics::set_hook(move |warning| warnings.borrow_mut().push(warning.into()));
+    }
+    let mailbox = Mailbox::new(|_| ());
+    let view = |n: u64| -> VNode<()> {
+        h::div()
+            .class("app")
+            .with((
+                h::keyed::ul().append(vec![(1, h::li()), (n, h::li()), (3, h::li())]),
+                h::button().on("click", |_| ()).on("click", |_| ()),
+                h::input().attribute("value", n.to_string()),
+                if n == 1 {
+                    h::p().ref_(|_| ()).into()
+                } else {
+                    VNode::from(h::span())
+                },
+            ))
+            .into()
+    };
+
+    let mut old = view(2);
+    create(&mut old, &mailbox);
+    let mut new = view(1);
+    new.patch(&mut old, &mailbox);
+    old = new;
+    let mut new = view(2);
+    new.patch(&mut old, &mailbox);
+
+    assert_eq!(
+        *warnings.borrow(),
+        [
+            "draco: listener for \"click\" registered twice at div.app > button",
+            "draco: \"value\" set as an attribute, which does not change the current value once \
+             the user has edited it; set it as a property instead at div.app > input",
+            "draco: duplicate key 1 among keyed children, which breaks reordering at div.app > ul",
+            "draco: <p> with a ref_ was replaced, so its ref_ fired again at div.app",
+        ]
+    );
+}
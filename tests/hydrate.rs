@@ -0,0 +1,96 @@
+use draco::dom::{self, memory, Backend, Memory, Ns};
+use draco::{html as h, Mailbox, VNode};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn view() -> VNode<()> {
+    h::div()
+        .class("a")
+        .with((
+            h::p().with(("Hello, ", "world", "", "!")),
+            h::button().on("click", |_| ()).with("Click"),
+        ))
+        .into()
+}
+
+// Builds what the browser would parse from server rendered HTML.
+fn element(name: &str, class: &str, children: Vec<memory::Node>) -> memory::Node {
+    let element = memory::Node::element(Ns::Html, name);
+    let node = dom::Node::from(element.clone());
+    if !class.is_empty() {
+        Memory.set_class_name(&node, class);
+    }
+    for child in children {
+        Memory.append_child(&node, &child.into());
+    }
+    element
+}
+
+fn hydrate(vnode: &mut VNode<()>, container: &memory::Node) -> dom::Node {
+    dom::set_backend(Memory);
+    let node = container.children().first().cloned().map(dom::Node::from);
+    vnode.hydrate(node, &container.clone().into(), &Mailbox::new(|_| ()))
+}
+
+#[test]
+fn t_adopts_server_rendered_nodes() {
+    let texts = ["Hello, ", "world", "", "!"];
+    let p = element(
+        "p",
+        "",
+        texts.iter().map(|t| memory::Node::text(t)).collect(),
+    );
+    let button = element("button", "", vec![memory::Node::text("Click")]);
+    let div = element("div", "a", vec![p.clone(), button.clone()]);
+    let container = element("div", "", vec![div.clone()]);
+
+    let node = hydrate(&mut view(), &container);
+
+    assert_eq!(node, div.clone().into());
+    assert_eq!(div.children(), [p.clone(), button.clone()]);
+    let texts = p
+        .children()
+        .iter()
+        .map(|text| text.text_content())
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["Hello, ", "world", "", "!"]);
+    assert_eq!(button.listeners(), ["click"]);
+}
+
+#[test]
+fn t_repairs_mismatches() {
+    let div = element(
+        "div",
+        "b",
+        vec![
+            element("span", "", vec![memory::Node::text("Hello")]),
+            element("p", "", vec![]),
+            element("i", "", vec![]),
+        ],
+    );
+    // Attributes the view does not have are removed.
+    Memory.set_attribute(&div.clone().into(), "title", "b");
+    Memory.set_style(&div.clone().into(), "color", "red", false);
+    let container = element("div", "", vec![div]);
+    let warnings = Rc::new(RefCell::new(Vec::<String>::new()));
+    {
+        let warnings = warnings.clone();
+        draco::diagnostics::set_hook(move |warning| warnings.borrow_mut().push(warning.into()));
+    }
+
+    hydrate(&mut view(), &container);
+    assert_eq!(
+        warnings.borrow()[..],
+        [
+            "draco: hydration mismatch: expected <p>, found span",
+            "draco: hydration mismatch: expected <button>, found p",
+            "draco: hydration mismatch: expected nothing, found i",
+        ]
+    );
+
+    let expected = element("div", "", vec![]);
+    dom::set_backend(Memory);
+    let node = view().create(&Mailbox::new(|_| ()));
+    Memory.append_child(&expected.clone().into(), &node);
+    assert_eq!(container.to_html(), expected.to_html());
+}
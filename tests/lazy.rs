@@ -12,7 +12,9 @@ fn t_same_arg_but_different_function() {
     let web_node = node_1.create(&mailbox);
     assert_eq!(
         web_node
-            .dyn_into::<web::Text>()
+            .as_web()
+            .unwrap_throw()
+            .dyn_ref::<web::Text>()
             .unwrap_throw()
             .text_content()
             .unwrap_throw(),
@@ -22,7 +24,9 @@ fn t_same_arg_but_different_function() {
     let web_node = node_2.patch(&mut node_1, &mailbox);
     assert_eq!(
         web_node
-            .dyn_into::<web::Text>()
+            .as_web()
+            .unwrap_throw()
+            .dyn_ref::<web::Text>()
             .unwrap_throw()
             .text_content()
             .unwrap_throw(),
@@ -0,0 +1,57 @@
+#![cfg(feature = "serde")]
+
+use draco::persist::{self, Persist};
+use draco::{html as h, VNode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+struct Todos {
+    items: Vec<String>,
+}
+
+impl draco::Application for Todos {
+    type Message = ();
+
+    fn view(&self) -> VNode<()> {
+        h::ul().into()
+    }
+}
+
+impl Persist for Todos {
+    const KEY: &'static str = "todos";
+    const VERSION: u32 = 2;
+
+    fn migrate(version: u32, model: serde_json::Value) -> Option<serde_json::Value> {
+        match version {
+            // Version 1 stored a single item.
+            1 => Some(serde_json::json!({ "items": [model.get("item")?] })),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn t_persist() {
+    let todos = Todos {
+        items: vec!["foo".into(), "bar".into()],
+    };
+    let json = persist::encode(&todos);
+    assert_eq!(json, r#"{"model":{"items":["foo","bar"]},"version":2}"#);
+    assert_eq!(persist::decode::<Todos>(&json), Some(todos));
+
+    assert_eq!(
+        persist::decode::<Todos>(r#"{"version":1,"model":{"item":"baz"}}"#),
+        Some(Todos {
+            items: vec!["baz".into()]
+        })
+    );
+    assert_eq!(
+        persist::decode::<Todos>(r#"{"version":0,"model":"baz"}"#),
+        None
+    );
+    assert_eq!(
+        persist::decode::<Todos>(r#"{"version":2,"model":{}}"#),
+        None
+    );
+    assert_eq!(persist::decode::<Todos>("nope"), None);
+}
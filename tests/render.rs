@@ -0,0 +1,113 @@
+use draco::{html as h, svg as s, Lazy, VNode};
+
+#[test]
+fn t_element() {
+    let node: VNode<()> = h::div()
+        .class("a b")
+        .id("main")
+        .with((
+            h::h1().with("Hello, <world> & friends!"),
+            h::a().href("/?a=1&b=\"2\"").with("link"),
+            h::button().on("click", |_| ()).with("Click"),
+        ))
+        .into();
+    assert_eq!(
+        node.to_html(),
+        "<div class=\"a b\" id=\"main\">\
+         <h1>Hello, &lt;world&gt; &amp; friends!</h1>\
+         <a href=\"/?a=1&amp;b=&quot;2&quot;\">link</a>\
+         <button>Click</button>\
+         </div>"
+    );
+}
+
+#[test]
+fn t_void_and_properties() {
+    let node: VNode<()> = h::form()
+        .with((
+            h::input().value("a \"b\"").checked(true).disabled(false),
+            h::br(),
+            h::textarea().value("<b>").readonly(true),
+            h::div().contenteditable(true),
+        ))
+        .into();
+    assert_eq!(
+        node.to_html(),
+        "<form>\
+         <input value=\"a &quot;b&quot;\" checked>\
+         <br>\
+         <textarea readonly>&lt;b&gt;</textarea>\
+         <div contenteditable=\"true\"></div>\
+         </form>"
+    );
+}
+
+#[test]
+fn t_svg_keyed_lazy_and_map() {
+    let node: VNode<i32> = h::div()
+        .with((
+            s::svg().with(s::circle().r("1")),
+            h::keyed::ul().with(((1, h::li().with(1)), (2, h::li().with(2)))),
+            Lazy::new(3, |&x| h::span().with(x).into()),
+        ))
+        .map(|()| 1)
+        .into();
+    assert_eq!(
+        node.to_html(),
+        "<div>\
+         <svg><circle r=\"1\"/></svg>\
+         <ul><li>1</li><li>2</li></ul>\
+         <span>3</span>\
+         </div>"
+    );
+}
+
+#[test]
+fn t_render_to_string() {
+    struct App(&'static str);
+
+    impl draco::Application for App {
+        type Message = ();
+
+        fn view(&self) -> VNode<Self::Message> {
+            h::p().with(self.0).into()
+        }
+    }
+
+    assert_eq!(draco::render_to_string(&App("Hi!")), "<p>Hi!</p>");
+}
+
+#[test]
+fn t_raw_text() {
+    let node: VNode<()> = h::div()
+        .with((
+            h::script().with(("if (a < b && c > d) {", " s = '</p></SCRIPT>'; }")),
+            h::style().with("a > b { content: \"&\"; }"),
+        ))
+        .into();
+    assert_eq!(
+        node.to_html(),
+        "<div>\
+         <script>if (a < b && c > d) { s = '</p><\\/SCRIPT>'; }</script>\
+         <style>a > b { content: \"&\"; }</style>\
+         </div>"
+    );
+}
+
+#[test]
+fn t_class_attribute() {
+    use draco::dom::{self, Memory};
+
+    let views: [fn() -> VNode<()>; 2] = [
+        || h::div().class("a").attribute("class", "b").into(),
+        || h::div().attribute("class", "b").class_if("c", true).into(),
+    ];
+    let expected = ["<div class=\"a\"></div>", "<div class=\"b c\"></div>"];
+    for (view, expected) in views.iter().zip(&expected) {
+        assert_eq!(view().to_html(), *expected);
+        // The same class as creating the element.
+        dom::set_backend(Memory);
+        let node = view().create(&draco::Mailbox::new(|_| ()));
+        assert_eq!(node.as_memory().unwrap().to_html(), *expected);
+    }
+}
@@ -0,0 +1,786 @@
+use draco::html as h;
+use draco::subscription::Interval;
+use draco::testing::Harness;
+use draco::{Cmd, Mailbox, Subscriptions, VNode};
+
+#[derive(Default)]
+struct Todos {
+    draft: String,
+    items: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Message {
+    Draft(String),
+    Add,
+    Remove(usize),
+}
+
+impl draco::Application for Todos {
+    type Message = Message;
+
+    fn update(&mut self, message: Message, _: &Mailbox<Message>) -> Cmd<Message> {
+        match message {
+            Message::Draft(draft) => self.draft = draft,
+            Message::Add => {
+                self.items.push(std::mem::take(&mut self.draft));
+                return Cmd::timeout(1000, || Message::Draft("".into())).label("clear");
+            }
+            Message::Remove(index) => {
+                self.items.remove(index);
+            }
+        }
+        Cmd::none()
+    }
+
+    fn subscriptions(&self) -> Subscriptions<Message> {
+        let subscriptions = Subscriptions::new();
+        if self.items.len() > 1 {
+            subscriptions.add("reminder", Interval::new(60_000), |()| Message::Remove(0))
+        } else {
+            subscriptions
+        }
+    }
+
+    fn view(&self) -> VNode<Message> {
+        h::form()
+            .id("todos")
+            .on("submit", |event| {
+                event.prevent_default();
+                Message::Add
+            })
+            .with((
+                h::input()
+                    .value(self.draft.clone())
+                    .on_input(Message::Draft),
+                h::ul().append(self.items.iter().enumerate().map(|(index, item)| {
+                    h::li().class("item").with((
+                        item.clone(),
+                        h::button()
+                            .on("click", move |_| Message::Remove(index))
+                            .with("x"),
+                    ))
+                })),
+            ))
+            .into()
+    }
+}
+
+#[test]
+fn t_harness() {
+    let harness = Harness::new(Todos::default());
+    assert_eq!(
+        harness.html(),
+        "<form id=\"todos\"><input value=\"\"><ul></ul></form>"
+    );
+
+    let input = harness.by_tag("input").remove(0);
+    let form = harness.by_id("todos").unwrap();
+    for item in &["foo", "bar"] {
+        harness.input(&input, item);
+        assert!(!harness.submit(&form));
+    }
+    assert_eq!(harness.by_class("item").len(), 2);
+    assert_eq!(harness.subscriptions(), ["reminder"]);
+    assert_eq!(harness.app().items, ["foo", "bar"]);
+
+    let foo = harness.by_text("foo").remove(0);
+    harness.click(&foo.children()[1]);
+    assert_eq!(
+        harness.html(),
+        "<form id=\"todos\"><input value=\"\"><ul><li class=\"item\">bar<button>x</button></li></ul></form>"
+    );
+    assert_eq!(
+        harness.messages(),
+        [
+            Message::Draft("foo".into()),
+            Message::Add,
+            Message::Draft("bar".into()),
+            Message::Add,
+            Message::Remove(0),
+        ]
+    );
+    assert!(harness.subscriptions().is_empty());
+    let commands = harness.commands();
+    assert_eq!(commands.len(), 2);
+    match commands[0] {
+        Cmd::Timeout {
+            timeout,
+            ref label,
+            ref f,
+        } => {
+            assert_eq!((timeout, label.as_deref()), (1000, Some("clear")));
+            assert_eq!(f(), Message::Draft("".into()));
+        }
+        ref cmd => panic!("{:?}", cmd),
+    }
+    assert_eq!(
+        format!("{:?}", commands[1]),
+        "Timeout { timeout: 1000, label: Some(\"clear\") }"
+    );
+    assert!(harness.commands().is_empty());
+}
+
+#[test]
+fn t_unmount() {
+    let harness = Harness::new(Todos::default());
+    let input = harness.by_tag("input").remove(0);
+    harness.input(&input, "foo");
+    harness.submit(&harness.by_id("todos").unwrap());
+    harness.input(&input, "bar");
+    harness.submit(&harness.by_id("todos").unwrap());
+    assert_eq!(harness.subscriptions(), ["reminder"]);
+
+    harness.unmount();
+    assert_eq!(harness.body().to_html(), "<body></body>");
+    assert!(harness.subscriptions().is_empty());
+
+    harness.send(Message::Remove(0));
+    harness.click(&input);
+    assert_eq!(harness.app().items, ["foo", "bar"]);
+    assert_eq!(harness.messages().len(), 4);
+}
+
+#[test]
+fn t_init() {
+    struct Loader(Vec<String>);
+
+    impl draco::Application for Loader {
+        type Message = Message;
+
+        fn init(&mut self, mailbox: &Mailbox<Message>) -> Cmd<Message> {
+            mailbox.send(Message::Draft("loading".into()));
+            Cmd::timeout(0, || Message::Add)
+        }
+
+        fn update(&mut self, message: Message, _: &Mailbox<Message>) -> Cmd<Message> {
+            if let Message::Draft(draft) = message {
+                self.0.push(draft);
+            }
+            Cmd::none()
+        }
+
+        fn view(&self) -> VNode<Message> {
+            h::p().with(self.0.join(",")).into()
+        }
+    }
+
+    let harness = Harness::new(Loader(Vec::new()));
+    assert_eq!(harness.html(), "<p>loading</p>");
+    assert_eq!(harness.messages(), [Message::Draft("loading".into())]);
+    assert!(matches!(
+        harness.commands()[..],
+        [Cmd::Timeout {
+            timeout: 0,
+            label: None,
+            ..
+        }]
+    ));
+}
+
+#[test]
+fn t_skip_render() {
+    use std::cell::Cell;
+
+    #[derive(Default)]
+    struct Views(Cell<usize>, i32);
+
+    impl draco::Application for Views {
+        type Message = i32;
+
+        fn update(&mut self, message: i32, _: &Mailbox<i32>) -> Cmd<i32> {
+            if message == self.1 {
+                return Cmd::skip_render();
+            }
+            if message < 0 {
+                return Cmd::batch(vec![Cmd::skip_render(), Cmd::timeout(10, || 0)]);
+            }
+            self.1 = message;
+            Cmd::none()
+        }
+
+        fn view(&self) -> VNode<i32> {
+            self.0.set(self.0.get() + 1);
+            h::button().on("click", |_| 1).with(self.1).into()
+        }
+    }
+
+    let harness = Harness::new(Views::default());
+    assert_eq!(harness.app().0.get(), 1);
+    harness.send(0);
+    assert_eq!(harness.app().0.get(), 1);
+    harness.click(&harness.root());
+    harness.click(&harness.root());
+    assert_eq!(harness.app().0.get(), 2);
+    assert_eq!(harness.html(), "<button>1</button>");
+    assert!(harness.commands().is_empty());
+
+    // The other commands of a batch still run.
+    harness.send(-1);
+    assert_eq!(harness.app().0.get(), 2);
+    assert!(matches!(
+        &harness.commands()[..],
+        [Cmd::Batch(cmds)] if matches!(cmds[..], [Cmd::SkipRender, Cmd::Timeout { timeout: 10, .. }])
+    ));
+    assert!(!Cmd::<()>::batch(vec![]).skips_render());
+}
+
+#[test]
+fn t_scheduler() {
+    use draco::Scheduler;
+    use std::cell::Cell;
+
+    #[derive(Default)]
+    struct Counter(Cell<usize>, i32);
+
+    impl draco::Application for Counter {
+        type Message = i32;
+
+        fn update(&mut self, message: i32, _: &Mailbox<i32>) -> Cmd<i32> {
+            self.1 += message;
+            Cmd::none()
+        }
+
+        fn view(&self) -> VNode<i32> {
+            self.0.set(self.0.get() + 1);
+            h::p().with(self.1).into()
+        }
+    }
+
+    let harness = Harness::with_scheduler(Counter::default(), Scheduler::Microtask);
+    harness.send(1);
+    harness.send(2);
+    assert_eq!(harness.html(), "<p>0</p>");
+    harness.tick();
+    assert_eq!(harness.html(), "<p>3</p>");
+    assert_eq!(harness.app().0.get(), 2);
+    harness.tick();
+    assert_eq!(harness.app().0.get(), 2);
+
+    let harness = Harness::with_scheduler(Counter::default(), Scheduler::AnimationFrame);
+    harness.send(1);
+    harness.tick();
+    harness.send(2);
+    assert_eq!(harness.html(), "<p>0</p>");
+    harness.frame();
+    assert_eq!(harness.html(), "<p>3</p>");
+    assert_eq!(harness.app().0.get(), 2);
+}
+
+#[test]
+fn t_middleware() {
+    use draco::{Middleware, MiddlewareChain};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Default)]
+    struct Total {
+        total: i32,
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    // Drops negative numbers and doubles the rest.
+    struct Filter;
+
+    impl Middleware<Total> for Filter {
+        fn before(&mut self, _: &Total, message: i32) -> Option<i32> {
+            if message < 0 {
+                None
+            } else {
+                Some(message * 2)
+            }
+        }
+    }
+
+    struct Logger(Rc<RefCell<Vec<String>>>, i32);
+
+    impl Middleware<Total> for Logger {
+        fn before(&mut self, app: &Total, message: i32) -> Option<i32> {
+            self.1 = app.total;
+            Some(message)
+        }
+
+        fn after(&mut self, app: &Total, message: Option<&i32>) {
+            let entry = match message {
+                Some(message) => format!("{} + {} -> {}", self.1, message, app.total),
+                None => format!("{} dropped", self.1),
+            };
+            self.0.borrow_mut().push(entry);
+        }
+    }
+
+    impl draco::Application for Total {
+        type Message = i32;
+
+        fn update(&mut self, message: i32, _: &Mailbox<i32>) -> Cmd<i32> {
+            self.total += message;
+            Cmd::none()
+        }
+
+        fn view(&self) -> VNode<i32> {
+            h::p().with(self.total).into()
+        }
+
+        fn middleware(&self) -> MiddlewareChain<Self> {
+            MiddlewareChain::new()
+                .with(Logger(self.log.clone(), 0))
+                .with(Filter)
+        }
+    }
+
+    let harness = Harness::new(Total::default());
+    for message in &[1, -5, 3] {
+        harness.send(*message);
+    }
+    assert_eq!(harness.html(), "<p>8</p>");
+    assert_eq!(harness.messages(), [2, 6]);
+    assert_eq!(
+        *harness.app().log.borrow(),
+        ["0 + 2 -> 2", "2 dropped", "2 + 6 -> 8"]
+    );
+}
+
+#[test]
+fn t_update_async() {
+    use draco::task::Model;
+    use draco::Task;
+    use std::cell::RefCell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::task::{Context, Poll};
+
+    // Resolves once a response has been pushed to it.
+    #[derive(Clone, Default)]
+    struct Response(Rc<RefCell<Option<Vec<String>>>>);
+
+    impl Future for Response {
+        type Output = Vec<String>;
+
+        fn poll(self: Pin<&mut Self>, _: &mut Context) -> Poll<Vec<String>> {
+            match self.0.borrow_mut().take() {
+                Some(results) => Poll::Ready(results),
+                None => Poll::Pending,
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct Search {
+        is_loading: bool,
+        results: Vec<String>,
+        pending: Rc<RefCell<Vec<(String, Response)>>>,
+    }
+
+    #[derive(Clone, Debug)]
+    enum Message {
+        Search(String),
+        Clear,
+    }
+
+    impl draco::Application for Search {
+        type Message = Message;
+
+        fn update(&mut self, message: Message, _: &Mailbox<Message>) -> Cmd<Message> {
+            if let Message::Clear = message {
+                self.results.clear();
+            }
+            Cmd::none()
+        }
+
+        fn update_async(message: Message, model: Model<Self>) -> Result<Task, Message> {
+            let query = match message {
+                Message::Search(query) => query,
+                message => return Err(message),
+            };
+            Ok(Task::new(async move {
+                let response = Response::default();
+                {
+                    let mut app = model.lock().await.unwrap();
+                    app.is_loading = true;
+                    let pending = app.pending.clone();
+                    pending.borrow_mut().push((query, response.clone()));
+                }
+                let results = response.await;
+                if let Some(mut app) = model.lock().await {
+                    app.is_loading = false;
+                    app.results = results;
+                }
+            })
+            .key("search"))
+        }
+
+        fn view(&self) -> VNode<Message> {
+            let status = if self.is_loading { "loading" } else { "idle" };
+            h::p().with((status, ": ", self.results.join(", "))).into()
+        }
+    }
+
+    let harness = Harness::new(Search::default());
+    let pending = harness.app().pending.clone();
+    harness.send(Message::Search("a".into()));
+    harness.poll();
+    assert_eq!(harness.html(), "<p>loading: </p>");
+    // Cancels the first search, which never completes even once its response arrives.
+    harness.send(Message::Search("ab".into()));
+    harness.poll();
+    let (first, second) = {
+        let mut pending = pending.borrow_mut();
+        let second = pending.pop().unwrap();
+        let first = pending.pop().unwrap();
+        assert_eq!((first.0.as_str(), second.0.as_str()), ("a", "ab"));
+        (first.1, second.1)
+    };
+    first.0.replace(Some(vec!["a".into(), "ab".into()]));
+    harness.poll();
+    assert_eq!(harness.html(), "<p>loading: </p>");
+    second.0.replace(Some(vec!["ab".into(), "abc".into()]));
+    harness.poll();
+    assert_eq!(harness.html(), "<p>idle: ab, abc</p>");
+    harness.send(Message::Clear);
+    assert_eq!(harness.html(), "<p>idle: </p>");
+}
+
+#[test]
+fn t_update_async_scheduled() {
+    use draco::task::Model;
+    use draco::{Scheduler, Task};
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::task::{Context, Poll};
+
+    // Resolves once opened.
+    #[derive(Clone, Default)]
+    struct Gate(Rc<Cell<bool>>);
+
+    impl Future for Gate {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _: &mut Context) -> Poll<()> {
+            if self.0.get() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        value: i32,
+        gate: Gate,
+    }
+
+    #[derive(Clone, Debug)]
+    enum Message {
+        Increment,
+        // Adds 10 once the gate opens, holding the model meanwhile.
+        Hold,
+    }
+
+    impl draco::Application for Counter {
+        type Message = Message;
+
+        fn update(&mut self, _: Message, _: &Mailbox<Message>) -> Cmd<Message> {
+            self.value += 1;
+            Cmd::none()
+        }
+
+        fn update_async(message: Message, model: Model<Self>) -> Result<Task, Message> {
+            if let Message::Increment = message {
+                return Err(message);
+            }
+            Ok(Task::new(async move {
+                let mut app = model.lock().await.unwrap();
+                let gate = app.gate.clone();
+                gate.await;
+                app.value += 10;
+            }))
+        }
+
+        fn view(&self) -> VNode<Message> {
+            h::p().with(self.value).into()
+        }
+    }
+
+    let harness = Harness::with_scheduler(Counter::default(), Scheduler::Microtask);
+    let gate = harness.app().gate.clone();
+    harness.send(Message::Increment);
+    harness.send(Message::Hold);
+    harness.send(Message::Hold);
+    harness.poll();
+    // The scheduled render waits for the first task to release the model, and the second task
+    // for the first.
+    harness.tick();
+    assert_eq!(harness.html(), "<p>0</p>");
+    gate.0.set(true);
+    harness.poll();
+    harness.tick();
+    assert_eq!(harness.html(), "<p>21</p>");
+}
+
+#[test]
+fn t_lock_during_update() {
+    use draco::task::Model;
+    use draco::Task;
+    use std::cell::RefCell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Wake, Waker};
+
+    #[derive(Debug, Default)]
+    struct Woken(AtomicBool);
+
+    impl Wake for Woken {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    thread_local! {
+        static MODEL: RefCell<Option<Model<Counter>>> = const { RefCell::new(None) };
+        static TASK: RefCell<Option<Pin<Box<dyn Future<Output = ()>>>>> = const { RefCell::new(None) };
+    }
+
+    #[derive(Default)]
+    struct Counter(i32);
+
+    #[derive(Clone, Debug)]
+    enum Message {
+        // Hands out the model for `Spawn`.
+        Share,
+        // Spawns a task which locks the model, polling it right away.
+        Spawn(Arc<Woken>),
+    }
+
+    impl draco::Application for Counter {
+        type Message = Message;
+
+        fn update(&mut self, message: Message, _: &Mailbox<Message>) -> Cmd<Message> {
+            if let Message::Spawn(woken) = message {
+                let model = MODEL.with(|model| model.take()).unwrap();
+                let mut task = Box::pin(async move {
+                    model.lock().await.unwrap().0 += 1;
+                });
+                let waker = Waker::from(woken);
+                assert!(task
+                    .as_mut()
+                    .poll(&mut Context::from_waker(&waker))
+                    .is_pending());
+                TASK.with(|t| t.replace(Some(task)));
+            }
+            Cmd::none()
+        }
+
+        fn update_async(message: Message, model: Model<Self>) -> Result<Task, Message> {
+            if let Message::Share = message {
+                MODEL.with(|m| m.replace(Some(model)));
+                return Ok(Task::new(async {}));
+            }
+            Err(message)
+        }
+
+        fn view(&self) -> VNode<Message> {
+            h::p().with(self.0).into()
+        }
+    }
+
+    let harness = Harness::new(Counter::default());
+    harness.send(Message::Share);
+    let woken = Arc::new(Woken::default());
+    harness.send(Message::Spawn(woken.clone()));
+    // No guard was held, so the task is woken once the update is done.
+    assert!(woken.0.load(Ordering::SeqCst));
+    let mut task = TASK.with(|t| t.take()).unwrap();
+    let waker = Waker::from(woken);
+    assert!(task
+        .as_mut()
+        .poll(&mut Context::from_waker(&waker))
+        .is_ready());
+    assert_eq!(harness.html(), "<p>1</p>");
+}
+
+#[test]
+fn t_delegate_events() {
+    #[derive(Default)]
+    struct Table {
+        rows: Vec<u32>,
+        log: Vec<String>,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Message {
+        Select(u32),
+        Delete(u32),
+        Table,
+        Focus(u32),
+    }
+
+    impl draco::Application for Table {
+        type Message = Message;
+
+        fn update(&mut self, message: Message, _: &Mailbox<Message>) -> Cmd<Message> {
+            match message {
+                Message::Delete(id) => self.rows.retain(|row| *row != id),
+                message => self.log.push(format!("{:?}", message)),
+            }
+            Cmd::none()
+        }
+
+        fn view(&self) -> VNode<Message> {
+            h::keyed::ul()
+                .on("click", |_| Message::Table)
+                .append(self.rows.iter().map(|&id| {
+                    let delete = h::button()
+                        .class("delete")
+                        .on("click", move |event| {
+                            event.stop_propagation();
+                            Message::Delete(id)
+                        })
+                        .with("x");
+                    let row = h::li()
+                        .on("click", move |_| Message::Select(id))
+                        .on("focus", move |_| Message::Focus(id))
+                        .with((h::span().with(id), delete));
+                    (id, row)
+                }))
+                .into()
+        }
+
+        fn delegate_events(&self) -> bool {
+            true
+        }
+    }
+
+    let harness = Harness::new(Table {
+        rows: vec![1, 2, 3],
+        log: Vec::new(),
+    });
+    assert_eq!(harness.body().listeners(), ["click"]);
+    assert!(harness.by_tag("ul")[0].listeners().is_empty());
+    // `focus` does not bubble, so it is still listened to on each row.
+    assert_eq!(harness.by_tag("li")[0].listeners(), ["focus"]);
+
+    harness.click(&harness.by_text("2")[0]);
+    harness.dispatch(&harness.by_tag("li")[0], "focus");
+    harness.click(&harness.by_class("delete")[2]);
+    harness.click(&harness.by_tag("ul")[0]);
+    assert_eq!(harness.html(), "<ul><li><span>1</span><button class=\"delete\">x</button></li><li><span>2</span><button class=\"delete\">x</button></li></ul>");
+    assert_eq!(
+        harness.app().log,
+        ["Select(2)", "Table", "Focus(1)", "Table"]
+    );
+
+    harness.unmount();
+    assert!(harness.body().listeners().is_empty());
+}
+
+#[test]
+fn t_delegate_events_during_patch() {
+    use draco::component::Scope;
+    use draco::dom::memory::{self, Event};
+    use draco::{Component, VComponent};
+
+    // Dispatches `focusout` at its props when destroyed, like a browser does at the focused
+    // element when the focus moves elsewhere while the parent is being patched.
+    struct Blur(memory::Node);
+
+    impl Component for Blur {
+        type Props = memory::Node;
+        type Message = ();
+        type Event = ();
+
+        fn create(node: &memory::Node) -> Self {
+            Blur(node.clone())
+        }
+
+        fn update(&mut self, _: &memory::Node, _: (), _: &Scope<Self>) -> Cmd<()> {
+            Cmd::none()
+        }
+
+        fn view(&self, _: &memory::Node) -> VNode<()> {
+            h::span().into()
+        }
+
+        fn destroyed(&mut self) {
+            self.0.dispatch(&Event::new("focusout"));
+        }
+    }
+
+    #[derive(Default)]
+    struct Form {
+        input: Option<memory::Node>,
+        log: Vec<String>,
+    }
+
+    #[derive(Clone, Debug)]
+    enum Message {
+        Mount(memory::Node),
+        Unmount,
+        Blurred,
+    }
+
+    impl draco::Application for Form {
+        type Message = Message;
+
+        fn update(&mut self, message: Message, _: &Mailbox<Message>) -> Cmd<Message> {
+            match message {
+                Message::Mount(node) => self.input = Some(node),
+                Message::Unmount => self.input = None,
+                Message::Blurred => self.log.push("blurred".into()),
+            }
+            Cmd::none()
+        }
+
+        fn view(&self) -> VNode<Message> {
+            let blur = match self.input {
+                Some(ref node) => VComponent::new::<Blur>(node.clone()).into(),
+                None => VNode::from(""),
+            };
+            h::div()
+                .with((h::input().on("focusout", |_| Message::Blurred), blur))
+                .into()
+        }
+
+        fn delegate_events(&self) -> bool {
+            true
+        }
+    }
+
+    let harness = Harness::new(Form::default());
+    let input = harness.by_tag("input").remove(0);
+    harness.send(Message::Mount(input.clone()));
+    assert_eq!(harness.by_tag("span").len(), 1);
+    harness.send(Message::Unmount);
+    assert!(harness.by_tag("span").is_empty());
+    assert_eq!(harness.app().log, ["blurred"]);
+}
+
+#[test]
+fn t_portal_selector() {
+    struct Modal;
+
+    impl draco::Application for Modal {
+        type Message = Message;
+
+        fn view(&self) -> VNode<Message> {
+            h::div()
+                .with((
+                    draco::portal("body").with(h::p().with("modal")),
+                    // Nothing matches, so its children are not in the document.
+                    draco::portal("#missing").with(h::p().with("missing")),
+                ))
+                .into()
+        }
+    }
+
+    let harness = Harness::new(Modal);
+    assert_eq!(
+        harness.body().to_html(),
+        "<body><div></div><div><p>modal</p></div></body>"
+    );
+}